@@ -0,0 +1,17 @@
+//! Copies the vendored JavaScript of `--format html` into `OUT_DIR`, so that
+//! it can be embedded into the binary. Missing files are embedded empty; the
+//! report then needs `--assets`.
+
+use std::{env, fs, path::Path};
+
+const ASSETS: &[&str] = &["cytoscape.min.js", "klay.js", "cytoscape-klay.js"];
+
+fn main() {
+    let out_dir = env::var("OUT_DIR").expect("Cargo sets OUT_DIR");
+    println!("cargo:rerun-if-changed=vendor");
+
+    for file_name in ASSETS {
+        let contents = fs::read_to_string(Path::new("vendor").join(file_name)).unwrap_or_default();
+        fs::write(Path::new(&out_dir).join(file_name), contents).expect("Cannot write OUT_DIR");
+    }
+}
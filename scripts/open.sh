@@ -1,3 +0,0 @@
-#!/bin/bash
-
-xdg-open http://localhost/index.html
@@ -1,3 +0,0 @@
-#!/bin/bash
-
-http-server . -p 80 --cors
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+# Fetches the JavaScript dependencies of `index.html` into `vendor/`, so that
+# `--format html` can inline them.
+
+set -e
+cd "$(dirname "$0")/../vendor"
+
+curl -fsSLO https://cdnjs.cloudflare.com/ajax/libs/cytoscape/3.23.0/cytoscape.min.js
+curl -fsSLO https://unpkg.com/klayjs@0.4.1/klay.js
+curl -fsSLO https://cdn.jsdelivr.net/gh/cytoscape/cytoscape.js-klay@9fab410e4823268b5fcf877b1f5af2798dd98371/cytoscape-klay.js
@@ -0,0 +1,9 @@
+pub mod coupling;
+pub mod cycles;
+pub mod diff;
+pub mod graph;
+pub mod orphans;
+pub mod query;
+pub mod rules;
+pub mod semver;
+pub mod unused;
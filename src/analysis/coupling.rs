@@ -0,0 +1,214 @@
+//! Coupling and stability metrics by [Robert C. Martin].
+//!
+//! For every module and crate:
+//!  - Afferent coupling (Ca) is the number of others that depend on it.
+//!  - Efferent coupling (Ce) is the number of others it depends on.
+//!  - Instability is `Ce / (Ca + Ce)`, or zero if both are zero.
+//!  - Abstractness is the ratio of traits to all traits and concrete types
+//!    (structs, enums, and unions), or zero if there are none.
+//!  - Distance from the main sequence is `|A + I - 1|`.
+//!
+//! Module metrics count only the module's own items, not its submodules.
+//! Dependencies come from the [`ModuleGraph`].
+//!
+//! [Robert C. Martin]: https://en.wikipedia.org/wiki/Software_package_metrics
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use super::graph::ModuleGraph;
+use crate::ir::{Mod, Package};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Coupling {
+    pub afferent: usize,
+    pub efferent: usize,
+    pub instability: f64,
+    pub abstractness: f64,
+    pub distance: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Metrics keyed by module IDs.
+    pub modules: BTreeMap<String, Coupling>,
+    /// Metrics keyed by crate IDs, e.g., `package::lib`.
+    pub crates: BTreeMap<String, Coupling>,
+}
+
+impl Coupling {
+    fn new(afferent: usize, efferent: usize, (traits, types): (usize, usize)) -> Self {
+        let instability = ratio(efferent, afferent + efferent);
+        let abstractness = ratio(traits, traits + types);
+        let distance = (abstractness + instability - 1.0).abs();
+        Self { afferent, efferent, instability, abstractness, distance }
+    }
+}
+
+pub fn compute(packages: &[Package]) -> Report {
+    let graph = ModuleGraph::new(packages);
+
+    let crate_ids = graph.ids.iter().map(|id| crate_id(id)).collect::<Vec<_>>();
+    let mut module_deps = vec![(BTreeSet::new(), BTreeSet::new()); graph.ids.len()];
+    let mut crate_deps = BTreeMap::<&str, (BTreeSet<&str>, BTreeSet<&str>)>::new();
+    for &(source, target) in graph.edges.keys() {
+        module_deps[source].1.insert(target);
+        module_deps[target].0.insert(source);
+
+        let (source_crate, target_crate) = (crate_ids[source], crate_ids[target]);
+        if source_crate != target_crate {
+            crate_deps.entry(source_crate).or_default().1.insert(target_crate);
+            crate_deps.entry(target_crate).or_default().0.insert(source_crate);
+        }
+    }
+
+    let mut modules = BTreeMap::new();
+    let mut crate_kinds = BTreeMap::<&str, (usize, usize)>::new();
+    for (i, module) in graph.modules.iter().enumerate() {
+        let (traits, types) = count_kinds(module);
+        let (afferent, efferent) = &module_deps[i];
+        modules.insert(
+            graph.ids[i].clone(),
+            Coupling::new(afferent.len(), efferent.len(), (traits, types)),
+        );
+
+        let crate_kinds = crate_kinds.entry(crate_ids[i]).or_default();
+        crate_kinds.0 += traits;
+        crate_kinds.1 += types;
+    }
+
+    let crates = crate_kinds
+        .into_iter()
+        .map(|(id, kinds)| {
+            let (afferent, efferent) = crate_deps.remove(id).unwrap_or_default();
+            (id.to_owned(), Coupling::new(afferent.len(), efferent.len(), kinds))
+        })
+        .collect();
+
+    Report { modules, crates }
+}
+
+/// Renders the metrics as a plain-text table.
+pub fn table(report: &Report) -> String {
+    let mut table = String::new();
+    for (title, metrics) in [("Crate", &report.crates), ("Module", &report.modules)] {
+        let width = metrics.keys().map(String::len).chain([title.len()]).max().unwrap_or_default();
+        table.push_str(&format!(
+            "{title:width$}  {:>5}  {:>5}  {:>5}  {:>5}  {:>5}\n",
+            "Ca", "Ce", "I", "A", "D"
+        ));
+        for (id, m) in metrics {
+            table.push_str(&format!(
+                "{id:width$}  {:>5}  {:>5}  {:>5.2}  {:>5.2}  {:>5.2}\n",
+                m.afferent, m.efferent, m.instability, m.abstractness, m.distance
+            ));
+        }
+        table.push('\n');
+    }
+    table
+}
+
+// Module IDs start with a package and a crate name.
+fn crate_id(module_id: &str) -> &str {
+    match module_id.match_indices("::").nth(1) {
+        Some((i, _)) => &module_id[..i],
+        None => module_id,
+    }
+}
+
+// Counts traits and concrete types.
+fn count_kinds(module: &Mod) -> (usize, usize) {
+    let items = &module.items;
+    let traits = items.traits.len() + items.trait_aliases.len();
+    let types = items.structs.len() + items.enums.len() + items.unions.len();
+    (traits, types)
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Struct, Trait, Use, Visibility};
+
+    #[test]
+    fn compute_metrics() {
+        let mut api = Mod::new("api");
+        api.items.traits.push(Trait {
+            name: "Service".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        let mut db = Mod::new("db");
+        db.items.structs.push(Struct {
+            name: "Pool".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        db.items.uses.push(Use {
+            vis: Visibility::Private,
+            repr: "use crate::api::Service;".to_owned(),
+            deps: vec!["app::lib::api::Service".to_owned()],
+        });
+        let mut lib = Mod::new("lib");
+        lib.items.mods.extend([api, db]);
+        let mut main = Mod::new("main");
+        main.items.uses.push(Use {
+            vis: Visibility::Private,
+            repr: "use app::db::Pool;".to_owned(),
+            deps: vec!["app::lib::db::Pool".to_owned()],
+        });
+        let packages = vec![Package { name: "app".to_owned(), crates: vec![lib, main] }];
+
+        let report = compute(&packages);
+        assert_eq!(
+            report.modules["app::lib::api"],
+            Coupling {
+                afferent: 1,
+                efferent: 0,
+                instability: 0.0,
+                abstractness: 1.0,
+                distance: 0.0
+            }
+        );
+        assert_eq!(
+            report.modules["app::lib::db"],
+            Coupling {
+                afferent: 1,
+                efferent: 1,
+                instability: 0.5,
+                abstractness: 0.0,
+                distance: 0.5
+            }
+        );
+        assert_eq!(
+            report.crates["app::lib"],
+            Coupling {
+                afferent: 1,
+                efferent: 0,
+                instability: 0.0,
+                abstractness: 0.5,
+                distance: 0.5
+            }
+        );
+        assert_eq!(
+            report.crates["app::main"],
+            Coupling {
+                afferent: 0,
+                efferent: 1,
+                instability: 1.0,
+                abstractness: 0.0,
+                distance: 0.0
+            }
+        );
+    }
+}
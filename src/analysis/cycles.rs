@@ -0,0 +1,301 @@
+//! Detection of module dependency cycles.
+//!
+//! Cycles are the [strongly connected components] of the [`ModuleGraph`].
+//!
+//! [strongly connected components]: https://en.wikipedia.org/wiki/Strongly_connected_component
+
+use std::collections::HashSet;
+
+use super::graph::ModuleGraph;
+use crate::ir::Package;
+
+/// A set of modules that depend on each other, directly or transitively.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Cycle {
+    pub modules: Vec<String>,
+    pub edges: Vec<CycleEdge>,
+}
+
+/// A dependency of one module on another within a cycle.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CycleEdge {
+    pub source: String,
+    pub target: String,
+    /// The dependency paths from `source` into `target`.
+    pub paths: Vec<String>,
+    /// The `use` statements in `source` that introduce the dependency, unless
+    /// it comes from paths in code only.
+    pub uses: Vec<String>,
+}
+
+/// Finds all module dependency cycles, sorted by their first module.
+pub fn find(packages: &[Package]) -> Vec<Cycle> {
+    let graph = ModuleGraph::new(packages);
+
+    let mut cycles = strongly_connected_components(&graph.adjacency())
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|component| cycle(&graph, component))
+        .collect::<Vec<_>>();
+    cycles.sort_by(|a, b| a.modules.cmp(&b.modules));
+    cycles
+}
+
+/// Returns the `(module, dependency path)` pairs of all edges within cycles.
+pub fn edge_set(cycles: &[Cycle]) -> HashSet<(String, String)> {
+    cycles
+        .iter()
+        .flat_map(|cycle| &cycle.edges)
+        .flat_map(|edge| edge.paths.iter().map(|path| (edge.source.clone(), path.clone())))
+        .collect()
+}
+
+/// Renders cycles as a human-readable report.
+pub fn report(cycles: &[Cycle]) -> String {
+    if cycles.is_empty() {
+        return "No module dependency cycles found.\n".to_owned();
+    }
+
+    let mut report = String::new();
+    for (i, cycle) in cycles.iter().enumerate() {
+        report.push_str(&format!("Cycle {}: {}\n", i + 1, cycle.modules.join(", ")));
+        for edge in &cycle.edges {
+            report.push_str(&format!("  {} -> {}\n", edge.source, edge.target));
+            for use_ in &edge.uses {
+                for line in use_.lines() {
+                    report.push_str(&format!("    {line}\n"));
+                }
+            }
+            if edge.uses.is_empty() {
+                for path in &edge.paths {
+                    report.push_str(&format!("    {path}\n"));
+                }
+            }
+        }
+    }
+    report
+}
+
+fn cycle(graph: &ModuleGraph, mut component: Vec<usize>) -> Cycle {
+    component.sort_by(|&a, &b| graph.ids[a].cmp(&graph.ids[b]));
+    let members = component.iter().copied().collect::<HashSet<_>>();
+
+    let mut edges = graph
+        .edges
+        .iter()
+        .filter(|((source, target), _)| members.contains(source) && members.contains(target))
+        .map(|(&(source, target), evidence)| CycleEdge {
+            source: graph.ids[source].clone(),
+            target: graph.ids[target].clone(),
+            paths: evidence.paths.clone(),
+            uses: evidence.uses.clone(),
+        })
+        .collect::<Vec<_>>();
+    edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+    Cycle { modules: component.into_iter().map(|i| graph.ids[i].clone()).collect(), edges }
+}
+
+// Tarjan's algorithm, with an explicit call stack so that deep module graphs
+// do not overflow the native one.
+fn strongly_connected_components(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut index = vec![UNVISITED; adjacency.len()];
+    let mut lowlink = vec![0; adjacency.len()];
+    let mut on_stack = vec![false; adjacency.len()];
+    let mut stack = vec![];
+    let mut next_index = 0;
+    let mut components = vec![];
+
+    for root in 0..adjacency.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        // Vertices paired with the positions of their next unvisited neighbours.
+        let mut call_stack = vec![(root, 0)];
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((v, next)) = call_stack.last_mut() {
+            let v = *v;
+            if let Some(&w) = adjacency[v].get(*next) {
+                *next += 1;
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    lowlink[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    call_stack.push((w, 0));
+                } else if on_stack[w] {
+                    lowlink[v] = lowlink[v].min(index[w]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[v]);
+            }
+            if lowlink[v] == index[v] {
+                let mut component = vec![];
+                loop {
+                    let w = stack.pop().expect("Must be on the stack");
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Mod, Struct, Use, Visibility};
+
+    fn module(name: &str, structs: &[&str], uses: &[(&str, &[&str])]) -> Mod {
+        let mut module = Mod::new(name);
+        for name in structs {
+            module.items.structs.push(Struct {
+                name: name.to_string(),
+                vis: Visibility::Public,
+                repr: String::new(),
+                ..Default::default()
+            });
+        }
+        for (repr, deps) in uses {
+            module.items.uses.push(Use {
+                vis: Visibility::Private,
+                repr: repr.to_string(),
+                deps: deps.iter().map(|dep| dep.to_string()).collect(),
+            });
+        }
+        module
+    }
+
+    #[test]
+    fn sccs() {
+        let adjacency = vec![vec![1], vec![2], vec![0], vec![2, 4], vec![3], vec![]];
+        let mut components = strongly_connected_components(&adjacency)
+            .into_iter()
+            .map(|mut component| {
+                component.sort();
+                component
+            })
+            .collect::<Vec<_>>();
+        components.sort();
+        assert_eq!(components, vec![vec![0, 1, 2], vec![3, 4], vec![5]]);
+    }
+
+    #[test]
+    fn find_cycles() {
+        let mut lib = Mod::new("lib");
+        lib.items.mods.push(module("a", &["A"], &[("use crate::b::B;", &["pkg::lib::b::B"])]));
+        lib.items.mods.push(module("b", &["B"], &[("use crate::a;", &["pkg::lib::a"])]));
+        lib.items.mods.push(module(
+            "c",
+            &[],
+            &[("use crate::a::A;", &["pkg::lib::a::A"]), ("use std::fmt;", &["std::lib::fmt"])],
+        ));
+        let packages = vec![Package { name: "pkg".to_owned(), crates: vec![lib] }];
+
+        let cycles = find(&packages);
+        assert_eq!(
+            cycles,
+            vec![Cycle {
+                modules: vec!["pkg::lib::a".to_owned(), "pkg::lib::b".to_owned()],
+                edges: vec![
+                    CycleEdge {
+                        source: "pkg::lib::a".to_owned(),
+                        target: "pkg::lib::b".to_owned(),
+                        paths: vec!["pkg::lib::b::B".to_owned()],
+                        uses: vec!["use crate::b::B;".to_owned()],
+                    },
+                    CycleEdge {
+                        source: "pkg::lib::b".to_owned(),
+                        target: "pkg::lib::a".to_owned(),
+                        paths: vec!["pkg::lib::a".to_owned()],
+                        uses: vec!["use crate::a;".to_owned()],
+                    },
+                ],
+            }]
+        );
+        assert!(
+            edge_set(&cycles).contains(&("pkg::lib::a".to_owned(), "pkg::lib::b::B".to_owned()))
+        );
+    }
+
+    #[test]
+    fn find_cycles_through_super() {
+        use crate::traverser::{self, Cache};
+
+        let proj = std::env::temp_dir().join(format!("cycles-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("src/lib.rs", "pub mod a;\npub mod b;\n"),
+            ("src/a.rs", "use super::b::c::C;\npub struct A;\n"),
+            (
+                "src/b.rs",
+                "pub mod c {\n    use super::super::a::{self, A};\n    pub struct C;\n}\n",
+            ),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let options = traverser::Options {
+            proj: proj.to_str().unwrap().to_owned(),
+            enable_edges: true,
+            ..Default::default()
+        };
+        let packages = traverser::traverse(&options, &Cache::default()).map(Iterator::collect);
+        std::fs::remove_dir_all(&proj).unwrap();
+        let packages: Vec<_> = packages.unwrap();
+
+        let cycles = find(&packages);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].modules, ["app::lib::a", "app::lib::b::c"]);
+        assert_eq!(
+            cycles[0].edges.iter().map(|edge| edge.uses.join(" ")).collect::<Vec<_>>(),
+            ["use super::b::c::C;", "use super::super::a::{self, A};"]
+        );
+    }
+
+    #[test]
+    fn find_cycles_through_variants_and_paths() {
+        // `Kind` is not in the IR, e.g., if filtered out by visibility.
+        let mut kind = module("kind", &[], &[]);
+        kind.paths.push("pkg::lib::db::connect".to_owned());
+        let db = module(
+            "db",
+            &[],
+            &[("use crate::kind::Kind::Table;", &["pkg::lib::kind::Kind::Table"])],
+        );
+        let mut lib = Mod::new("lib");
+        lib.items.mods.extend([db, kind]);
+        let packages = vec![Package { name: "pkg".to_owned(), crates: vec![lib] }];
+
+        let cycles = find(&packages);
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].modules, ["pkg::lib::db", "pkg::lib::kind"]);
+        assert_eq!(
+            report(&cycles),
+            "Cycle 1: pkg::lib::db, pkg::lib::kind\n  pkg::lib::db -> pkg::lib::kind\n    use \
+             crate::kind::Kind::Table;\n  pkg::lib::kind -> pkg::lib::db\n    \
+             pkg::lib::db::connect\n"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! Structural differences between two versions of a project.
+//!
+//! Vertices are packages, crates, modules, and items, compared by their
+//! [IDs](crate::ir::id) and `repr`s. Edges are module dependencies, compared by
+//! their source modules and dependency paths.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use displaydoc::Display;
+
+use crate::ir::{id, Package};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Display)]
+pub enum Status {
+    /// added
+    Added,
+    /// removed
+    Removed,
+    /// changed
+    Changed,
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Diff {
+    /// Sorted by ID.
+    pub vertices: Vec<VertexChange>,
+    /// Sorted by source and target.
+    pub edges: Vec<EdgeChange>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct VertexChange {
+    pub status: Status,
+    /// `package`, `crate`, `mod`, or an item kind, e.g., `fn`.
+    pub kind: &'static str,
+    pub id: String,
+    /// The old `repr` of a changed item.
+    pub old_repr: Option<String>,
+    /// The new `repr` of a changed item.
+    pub new_repr: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct EdgeChange {
+    pub status: Status,
+    pub source: String,
+    pub target: String,
+}
+
+// Vertex kinds and `repr`s by IDs, and edges.
+#[derive(Default)]
+struct Snapshot<'a> {
+    vertices: BTreeMap<String, (&'static str, &'a str)>,
+    edges: BTreeSet<(String, &'a str)>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty() && self.edges.is_empty()
+    }
+}
+
+pub fn compute(old: &[Package], new: &[Package]) -> Diff {
+    let (old, new) = (snapshot(old), snapshot(new));
+    let mut diff = Diff::default();
+
+    for (id, &(kind, repr)) in &old.vertices {
+        let (status, new_repr) = match new.vertices.get(id) {
+            None => (Status::Removed, None),
+            Some(&(new_kind, new_repr)) if (kind, repr) != (new_kind, new_repr) => {
+                (Status::Changed, Some(new_repr.to_owned()))
+            }
+            Some(_) => continue,
+        };
+        let old_repr = (status == Status::Changed).then(|| repr.to_owned());
+        diff.vertices.push(VertexChange { status, kind, id: id.clone(), old_repr, new_repr });
+    }
+    for (id, &(kind, _)) in &new.vertices {
+        if !old.vertices.contains_key(id) {
+            diff.vertices.push(VertexChange {
+                status: Status::Added,
+                kind,
+                id: id.clone(),
+                old_repr: None,
+                new_repr: None,
+            });
+        }
+    }
+    diff.vertices.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let edge = |status, (source, target): &(String, &str)| EdgeChange {
+        status,
+        source: source.clone(),
+        target: target.to_string(),
+    };
+    diff.edges.extend(old.edges.difference(&new.edges).map(|e| edge(Status::Removed, e)));
+    diff.edges.extend(new.edges.difference(&old.edges).map(|e| edge(Status::Added, e)));
+    diff.edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+    diff
+}
+
+/// Renders a diff as a human-readable report.
+pub fn report(diff: &Diff) -> String {
+    if diff.is_empty() {
+        return "No structural changes found.\n".to_owned();
+    }
+
+    let sign = |status| match status {
+        Status::Added => '+',
+        Status::Removed => '-',
+        Status::Changed => '~',
+    };
+
+    let mut report = String::new();
+    for change in &diff.vertices {
+        let path = id::path(&change.id);
+        report.push_str(&format!("{} {} {path}\n", sign(change.status), change.kind));
+        for (sign, repr) in [('-', &change.old_repr), ('+', &change.new_repr)] {
+            for line in repr.iter().flat_map(|repr| repr.lines()) {
+                report.push_str(&format!("    {sign} {line}\n"));
+            }
+        }
+    }
+    for change in &diff.edges {
+        report.push_str(&format!(
+            "{} dep {} -> {}\n",
+            sign(change.status),
+            change.source,
+            change.target
+        ));
+    }
+
+    let count = |status| {
+        diff.vertices.iter().filter(|change| change.status == status).count()
+            + diff.edges.iter().filter(|change| change.status == status).count()
+    };
+    report.push_str(&format!(
+        "\n{} added, {} removed, {} changed.\n",
+        count(Status::Added),
+        count(Status::Removed),
+        count(Status::Changed)
+    ));
+    report
+}
+
+fn snapshot(packages: &[Package]) -> Snapshot<'_> {
+    let mut snapshot = Snapshot::default();
+
+    for package in packages {
+        snapshot.vertices.insert(package.name.clone(), ("package", ""));
+        package.walk_mods(&mut |id, module| {
+            // Crate IDs consist of a package and a crate name.
+            let kind = if id.matches("::").count() == 1 { "crate" } else { "mod" };
+            snapshot.vertices.insert(id.to_owned(), (kind, ""));
+            for item in module.items.named_items() {
+                snapshot.vertices.insert(item.id(id), (item.kind, item.repr));
+            }
+            for dep in &module.deps {
+                snapshot.edges.insert((id.to_owned(), dep));
+            }
+        });
+    }
+
+    snapshot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Fn, Mod, Struct, Visibility};
+
+    fn package(foo_repr: &str, with_bar: bool, deps: &[&str]) -> Vec<Package> {
+        let mut lib = Mod::new("lib");
+        lib.items.fns.push(Fn {
+            name: "foo".to_owned(),
+            vis: Visibility::Public,
+            repr: foo_repr.to_owned(),
+            lines: 1,
+            complexity: 1,
+            ..Default::default()
+        });
+        if with_bar {
+            lib.items.structs.push(Struct {
+                name: "Bar".to_owned(),
+                vis: Visibility::Public,
+                repr: "pub struct Bar;".to_owned(),
+                ..Default::default()
+            });
+        }
+        lib.deps.extend(deps.iter().map(|dep| dep.to_string()));
+        vec![Package { name: "pkg".to_owned(), crates: vec![lib] }]
+    }
+
+    #[test]
+    fn compute_diff() {
+        let old = package("pub fn foo(x: i32) {}", true, &["std::lib::fmt"]);
+        let new = package("pub fn foo(x: u32) {}", false, &["std::lib::io"]);
+
+        let diff = compute(&old, &new);
+        assert_eq!(
+            diff.vertices,
+            vec![
+                VertexChange {
+                    status: Status::Removed,
+                    kind: "struct",
+                    id: "pkg::lib::Bar#struct".to_owned(),
+                    old_repr: None,
+                    new_repr: None,
+                },
+                VertexChange {
+                    status: Status::Changed,
+                    kind: "fn",
+                    id: "pkg::lib::foo#fn".to_owned(),
+                    old_repr: Some("pub fn foo(x: i32) {}".to_owned()),
+                    new_repr: Some("pub fn foo(x: u32) {}".to_owned()),
+                },
+            ]
+        );
+        assert_eq!(
+            report(&diff),
+            "- struct pkg::lib::Bar\n\
+             ~ fn pkg::lib::foo\n    - pub fn foo(x: i32) {}\n    + pub fn foo(x: u32) {}\n\
+             - dep pkg::lib -> std::lib::fmt\n\
+             + dep pkg::lib -> std::lib::io\n\
+             \n1 added, 2 removed, 1 changed.\n"
+        );
+        assert!(compute(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+//! The module dependency graph.
+//!
+//! Dependencies come from `use` statements and paths in code. Each one is
+//! attributed to the innermost traversed module that contains it, e.g.,
+//! `pkg::lib::db::Kind::A` to `pkg::lib::db`, even if the item itself is not
+//! in the IR. Dependencies outside traversed modules are ignored.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::ir::{Mod, Package};
+
+#[derive(Default)]
+pub struct ModuleGraph<'a> {
+    /// Module IDs, in the order of traversal.
+    pub ids: Vec<String>,
+    /// Modules, in the same order as `ids`.
+    pub modules: Vec<&'a Mod>,
+    /// Dependencies, keyed by source and target module indices.
+    pub edges: BTreeMap<(usize, usize), Evidence>,
+}
+
+/// What makes one module depend on another.
+#[derive(Default)]
+pub struct Evidence {
+    /// The dependency paths from the source module into the target one.
+    pub paths: Vec<String>,
+    /// The `use` statements in the source module that introduce the
+    /// dependency, unless it comes from paths in code only.
+    pub uses: Vec<String>,
+}
+
+impl<'a> ModuleGraph<'a> {
+    pub fn new(packages: &'a [Package]) -> Self {
+        let mut graph = Self::default();
+
+        // Maps module IDs to their indices.
+        let mut indices = HashMap::new();
+        for package in packages {
+            package.walk_mods(&mut |id, module| {
+                indices.insert(id.to_owned(), graph.ids.len());
+                graph.ids.push(id.to_owned());
+                graph.modules.push(module);
+            });
+        }
+
+        for (source, module) in graph.modules.iter().enumerate() {
+            let uses = module.items.uses.iter().flat_map(|use_| {
+                use_.deps.iter().map(move |path| (path, Some(use_.repr.as_str())))
+            });
+            for (path, use_) in uses.chain(module.paths.iter().map(|path| (path, None))) {
+                let Some(target) = owner(&indices, path) else {
+                    continue;
+                };
+                if target == source {
+                    continue;
+                }
+
+                let evidence = graph.edges.entry((source, target)).or_default();
+                push_unique(&mut evidence.paths, path);
+                if let Some(use_) = use_ {
+                    push_unique(&mut evidence.uses, use_);
+                }
+            }
+        }
+
+        graph
+    }
+
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![vec![]; self.ids.len()];
+        for &(source, target) in self.edges.keys() {
+            adjacency[source].push(target);
+        }
+        adjacency
+    }
+}
+
+// The index of the innermost module that contains `path`, by the longest
+// prefix of `path` that is a module ID.
+fn owner(indices: &HashMap<String, usize>, mut path: &str) -> Option<usize> {
+    loop {
+        if let Some(&index) = indices.get(path) {
+            return Some(index);
+        }
+        path = path.rsplit_once("::")?.0;
+    }
+}
+
+fn push_unique(acc: &mut Vec<String>, value: &str) {
+    if !acc.iter().any(|existing| existing == value) {
+        acc.push(value.to_owned());
+    }
+}
@@ -0,0 +1,82 @@
+//! Detection of orphan source files, which no `mod` declaration reaches.
+//!
+//! All `.rs` files under the `src/` directories of workspace members are
+//! compared with the files of traversed modules. `src/bin/` is skipped, since
+//! binary targets there are not traversed yet. Modules declared inside macro
+//! invocations, e.g., `cfg_feature! { mod foo; }`, are not followed either,
+//! so their files show up as orphans.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use crate::ir::Package;
+
+/// Returns the orphan files of `members`, sorted.
+pub fn find(members: &[PathBuf], packages: &[Package]) -> Vec<PathBuf> {
+    let mut reached = HashSet::new();
+    for package in packages {
+        package.walk_mods(&mut |_, module| {
+            reached.extend(module.file.iter().chain(&module.alternative_files).cloned())
+        });
+    }
+
+    let mut orphans = vec![];
+    for member in members {
+        let src = member.join("src");
+        collect_sources(&mut orphans, &src, &src.join("bin"));
+    }
+    orphans.retain(|file| !reached.contains(&canonicalize(file)));
+    orphans.sort();
+    orphans
+}
+
+fn collect_sources(acc: &mut Vec<PathBuf>, dir: &Path, skipped: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if path != skipped {
+                collect_sources(acc, &path, skipped);
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            acc.push(path);
+        }
+    }
+}
+
+// Module files are canonicalized by the traverser.
+fn canonicalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Mod;
+
+    #[test]
+    fn find_orphans() {
+        let member = std::env::temp_dir().join(format!("orphans-{}", std::process::id()));
+        for file in ["src/lib.rs", "src/db.rs", "src/old.rs", "src/db/legacy.rs", "src/bin/cli.rs"]
+        {
+            let path = member.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let mut db = Mod::new("db");
+        db.file = Some(canonicalize(&member.join("src/db.rs")));
+        let mut lib = Mod::new("lib");
+        lib.file = Some(canonicalize(&member.join("src/lib.rs")));
+        lib.items.mods.push(db);
+        let packages = vec![Package { name: "pkg".to_owned(), crates: vec![lib] }];
+
+        let orphans = find(std::slice::from_ref(&member), &packages);
+        std::fs::remove_dir_all(&member).unwrap();
+        assert_eq!(orphans, vec![member.join("src/db/legacy.rs"), member.join("src/old.rs")]);
+    }
+}
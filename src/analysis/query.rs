@@ -0,0 +1,345 @@
+//! A small query language over the IR.
+//!
+//! Queries:
+//!  - `deps-of PATH`: the dependencies of the modules at `PATH`.
+//!  - `rdeps-of PATH`: the dependencies of all modules on `PATH` or anything
+//!    inside it.
+//!  - `path-from PATH to PATH`: a shortest dependency path between modules.
+//!  - `items [kind=KIND] [in PATTERN]`: items, optionally of a kind, e.g.,
+//!    `trait`, and within a [`PathPattern`].
+//!  - `impls-of TRAIT`: implementations of a trait, including derived ones.
+//!
+//! A `PATH` matches the vertex IDs that end with it, e.g., `ir::Mod` matches
+//! `pkg::main::ir::Mod`. A leading `crate` anchors it at crate roots.
+
+use std::{collections::VecDeque, str::FromStr};
+
+use anyhow::bail;
+use serde::Serialize;
+
+use super::graph::ModuleGraph;
+use crate::{
+    ir::{id, Package},
+    pattern::PathPattern,
+};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Query {
+    DepsOf(String),
+    RdepsOf(String),
+    PathFrom(String, String),
+    Items { kind: Option<String>, scope: Option<PathPattern> },
+    ImplsOf(String),
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Deps(Vec<Dep>),
+    /// Module IDs from the source to the target, or nothing.
+    Path(Vec<String>),
+    Items(Vec<ItemRef>),
+    Impls(Vec<ImplRef>),
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct Dep {
+    /// The depending module.
+    pub source: String,
+    /// The dependency path.
+    pub target: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct ItemRef {
+    pub kind: &'static str,
+    /// The ID of the item, as in the graph outputs, e.g., `pkg::lib::Mod#struct`.
+    pub id: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct ImplRef {
+    /// The module containing the implementation.
+    pub module: String,
+    #[serde(rename = "trait")]
+    pub trait_: String,
+    pub self_ty: String,
+    pub derived: bool,
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(query: &str) -> anyhow::Result<Self> {
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        Ok(match words[..] {
+            ["deps-of", path] => Self::DepsOf(path.to_owned()),
+            ["rdeps-of", path] => Self::RdepsOf(path.to_owned()),
+            ["path-from", from, "to", to] => Self::PathFrom(from.to_owned(), to.to_owned()),
+            ["impls-of", trait_] => Self::ImplsOf(trait_.to_owned()),
+            ["items", ref filters @ ..] => {
+                let (mut kind, mut scope) = (None, None);
+                let mut filters = filters.iter();
+                while let Some(&filter) = filters.next() {
+                    match (filter.strip_prefix("kind="), filter) {
+                        (Some(value), _) => kind = Some(value.to_owned()),
+                        (None, "in") => match filters.next() {
+                            Some(pattern) => scope = Some(PathPattern::from(pattern.to_string())),
+                            None => bail!("Expected a pattern after `in`"),
+                        },
+                        (None, _) => bail!("Unexpected filter `{filter}`"),
+                    }
+                }
+                Self::Items { kind, scope }
+            }
+            _ => bail!(
+                "Unknown query `{query}`. Expected `deps-of PATH`, `rdeps-of PATH`, `path-from \
+                 PATH to PATH`, `items [kind=KIND] [in PATTERN]`, or `impls-of TRAIT`."
+            ),
+        })
+    }
+}
+
+pub fn run(query: &Query, packages: &[Package]) -> Answer {
+    match query {
+        Query::DepsOf(path) => {
+            let mut deps = vec![];
+            walk_mods(packages, |id, module| {
+                if ends_with(id, path) {
+                    deps.extend(
+                        module
+                            .deps
+                            .iter()
+                            .map(|dep| Dep { source: id.to_owned(), target: dep.clone() }),
+                    );
+                }
+            });
+            Answer::Deps(deps)
+        }
+        Query::RdepsOf(path) => {
+            let mut deps = vec![];
+            walk_mods(packages, |id, module| {
+                deps.extend(
+                    module
+                        .deps
+                        .iter()
+                        .filter(|dep| is_within(dep, path))
+                        .map(|dep| Dep { source: id.to_owned(), target: dep.clone() }),
+                );
+            });
+            Answer::Deps(deps)
+        }
+        Query::PathFrom(from, to) => Answer::Path(shortest_path(packages, from, to)),
+        Query::Items { kind, scope } => {
+            let mut items = vec![];
+            walk_mods(packages, |id, module| {
+                for item in module.items.named_items() {
+                    if kind.as_ref().is_none_or(|kind| kind == item.kind)
+                        && scope.as_ref().is_none_or(|scope| scope.matches(&item.path(id)))
+                    {
+                        items.push(ItemRef { kind: item.kind, id: item.id(id) });
+                    }
+                }
+            });
+            Answer::Items(items)
+        }
+        Query::ImplsOf(trait_) => {
+            let mut impls = vec![];
+            walk_mods(packages, |id, module| {
+                for impl_ in &module.items.impls {
+                    let Some(impl_trait) = &impl_.trait_ else {
+                        continue;
+                    };
+                    // Generic arguments do not matter, e.g., `From<u32>`.
+                    let trait_path = impl_trait.split('<').next().unwrap_or_default();
+                    if ends_with(trait_path, trait_) {
+                        impls.push(ImplRef {
+                            module: id.to_owned(),
+                            trait_: impl_trait.clone(),
+                            self_ty: impl_.self_ty.clone(),
+                            derived: impl_.derived,
+                        });
+                    }
+                }
+            });
+            Answer::Impls(impls)
+        }
+    }
+}
+
+/// Renders an answer as human-readable text.
+pub fn render(answer: &Answer) -> String {
+    let lines = match answer {
+        Answer::Deps(deps) => {
+            deps.iter().map(|dep| format!("{} -> {}", dep.source, dep.target)).collect()
+        }
+        Answer::Path(path) if path.is_empty() => vec![],
+        Answer::Path(path) => vec![path.join(" -> ")],
+        Answer::Items(items) => {
+            items.iter().map(|item| format!("{} {}", item.kind, id::path(&item.id))).collect()
+        }
+        Answer::Impls(impls) => impls
+            .iter()
+            .map(|impl_| {
+                format!(
+                    "{}: impl {} for {}{}",
+                    impl_.module,
+                    impl_.trait_,
+                    impl_.self_ty,
+                    if impl_.derived { " (derived)" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>(),
+    };
+    if lines.is_empty() {
+        return "Nothing found.\n".to_owned();
+    }
+    lines.into_iter().map(|line| line + "\n").collect()
+}
+
+fn walk_mods<'a>(packages: &'a [Package], mut f: impl FnMut(&str, &'a crate::ir::Mod)) {
+    for package in packages {
+        package.walk_mods(&mut f);
+    }
+}
+
+// Breadth-first search from all modules matching `from` to any module matching
+// or containing `to`.
+fn shortest_path(packages: &[Package], from: &str, to: &str) -> Vec<String> {
+    let graph = ModuleGraph::new(packages);
+    let is_target = |i: usize| {
+        ends_with(&graph.ids[i], to)
+            || graph.modules[i]
+                .items
+                .named_items()
+                .iter()
+                .any(|item| ends_with(&item.path(&graph.ids[i]), to))
+    };
+
+    let adjacency = graph.adjacency();
+    let mut parents = vec![None::<usize>; graph.ids.len()];
+    let mut visited = vec![false; graph.ids.len()];
+    let mut queue = VecDeque::new();
+    for (i, id) in graph.ids.iter().enumerate() {
+        if ends_with(id, from) {
+            visited[i] = true;
+            queue.push_back(i);
+        }
+    }
+
+    while let Some(v) = queue.pop_front() {
+        if is_target(v) {
+            let mut path = vec![graph.ids[v].clone()];
+            let mut current = v;
+            while let Some(parent) = parents[current] {
+                path.push(graph.ids[parent].clone());
+                current = parent;
+            }
+            path.reverse();
+            return path;
+        }
+        for &w in &adjacency[v] {
+            if !visited[w] {
+                visited[w] = true;
+                parents[w] = Some(v);
+                queue.push_back(w);
+            }
+        }
+    }
+    vec![]
+}
+
+// Whether `id` ends with the segments of `path`.
+fn ends_with(id: &str, path: &str) -> bool {
+    let id = id.split("::").collect::<Vec<_>>();
+    match path.strip_prefix("crate").filter(|rest| rest.is_empty() || rest.starts_with("::")) {
+        // Module IDs start with a package and a crate name.
+        Some(path) => {
+            let path = path.split("::").filter(|segment| !segment.is_empty()).collect::<Vec<_>>();
+            id.len() == path.len() + 2 && id[2..] == path[..]
+        }
+        None => id.ends_with(&path.split("::").collect::<Vec<_>>()),
+    }
+}
+
+// Whether `id` is at `path` or inside it.
+fn is_within(id: &str, path: &str) -> bool {
+    ends_with(id, path) || id.match_indices("::").any(|(i, _)| ends_with(&id[..i], path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Impl, Mod, Struct, Trait, Use, Visibility};
+
+    fn packages() -> Vec<Package> {
+        let mut ir = Mod::new("ir");
+        ir.items.traits.push(Trait {
+            name: "Visit".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        ir.items.structs.push(Struct {
+            name: "Mod".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        ir.items.impls.push(Impl {
+            trait_: Some("serde::Serialize".to_owned()),
+            self_ty: "Mod".to_owned(),
+            derived: true,
+            repr: String::new(),
+            ..Default::default()
+        });
+        let mut traverser = Mod::new("traverser");
+        traverser.items.uses.push(Use {
+            vis: Visibility::Private,
+            repr: "use crate::ir::Mod;".to_owned(),
+            deps: vec!["app::main::ir::Mod".to_owned()],
+        });
+        traverser.deps.push("app::main::ir::Mod".to_owned());
+        let mut main = Mod::new("main");
+        main.items.uses.push(Use {
+            vis: Visibility::Private,
+            repr: "use crate::traverser;".to_owned(),
+            deps: vec!["app::main::traverser".to_owned()],
+        });
+        main.deps.push("app::main::traverser".to_owned());
+        main.items.mods.extend([ir, traverser]);
+        vec![Package { name: "app".to_owned(), crates: vec![main] }]
+    }
+
+    fn query(query: &str) -> String {
+        render(&run(&query.parse().unwrap(), &packages()))
+    }
+
+    #[test]
+    fn queries() {
+        assert_eq!(
+            query("deps-of crate::traverser"),
+            "app::main::traverser -> app::main::ir::Mod\n"
+        );
+        assert_eq!(query("rdeps-of ir"), "app::main::traverser -> app::main::ir::Mod\n");
+        assert_eq!(
+            query("path-from main to ir::Mod"),
+            "app::main -> app::main::traverser -> app::main::ir\n"
+        );
+        assert_eq!(query("items kind=trait in app::*"), "trait app::main::ir::Visit\n");
+        assert_eq!(query("items kind=fn"), "Nothing found.\n");
+        assert_eq!(
+            run(&"items in **::Visit".parse().unwrap(), &packages()),
+            Answer::Items(vec![ItemRef {
+                kind: "trait",
+                id: "app::main::ir::Visit#trait".to_owned()
+            }])
+        );
+        assert_eq!(
+            query("impls-of Serialize"),
+            "app::main::ir: impl serde::Serialize for Mod (derived)\n"
+        );
+        assert!("items kind=fn in".parse::<Query>().is_err());
+        assert!("callers-of foo".parse::<Query>().is_err());
+    }
+}
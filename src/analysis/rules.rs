@@ -0,0 +1,424 @@
+//! Architecture rules, checked against the IR.
+//!
+//! Rules live in the `[rules]` table of `ontologist.toml`:
+//!
+//! ```toml
+//! # `app::lib::domain` must not depend on `app::lib::infra`.
+//! [[rules.forbidden-dependencies]]
+//! from = "app::lib::domain"
+//! to = "app::lib::infra"
+//!
+//! # Package `app_core` must not depend on package `app_web`, matched on the
+//! # first segments of module IDs and dependencies.
+//! [[rules.forbidden-package-dependencies]]
+//! from = "app_core"
+//! to = "app_web"
+//!
+//! # Only `api` may be public at the root of `app::lib`.
+//! [[rules.public-root-items]]
+//! crate = "app::lib"
+//! allow = ["api"]
+//! ```
+//!
+//! All paths are [`PathPattern`]s over vertex IDs. Dependencies are taken
+//! from `use` statements and from paths in code, e.g.,
+//! `crate::infra::Db::connect()`, so they need not resolve to traversed items:
+//! a dependency on an external crate `foo` is a path starting with `foo::`.
+//! Relative paths, e.g., `super::infra::Db`, are resolved against the module,
+//! so they are matched like their `crate::` equivalents.
+
+use std::{fmt, path::Path};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+    ir::{Mod, Package, Visibility},
+    pattern::PathPattern,
+    syn_util,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Rules {
+    #[serde(default)]
+    pub forbidden_dependencies: Vec<ForbiddenDependency>,
+    #[serde(default)]
+    pub forbidden_package_dependencies: Vec<ForbiddenDependency>,
+    #[serde(default)]
+    pub public_root_items: Vec<PublicRootItems>,
+}
+
+/// Modules matching `from` must not depend on paths matching `to`. For
+/// package dependencies, both match package names only.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForbiddenDependency {
+    pub from: PathPattern,
+    pub to: PathPattern,
+}
+
+/// Only items matching `allow` may be public at the roots of crates matching
+/// `crate`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PublicRootItems {
+    #[serde(rename = "crate")]
+    pub crate_: PathPattern,
+    pub allow: Vec<PathPattern>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Violation {
+    /// The name of the violated rule kind, e.g., `forbidden-dependencies`.
+    pub rule: &'static str,
+    pub message: String,
+    /// The offending code, if any.
+    pub evidence: Option<String>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error[{}]: {}", self.rule, self.message)?;
+        if let Some(evidence) = &self.evidence {
+            for line in evidence.lines() {
+                write!(f, "\n    {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rules: Rules,
+}
+
+/// Reads the `[rules]` table of a configuration file.
+pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Rules> {
+    let path = path.as_ref();
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let file = toml::from_str::<RulesFile>(&contents)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    Ok(file.rules)
+}
+
+/// Checks all rules, returning the violations in the order of traversal.
+pub fn check(rules: &Rules, packages: &[Package]) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for package in packages {
+        package.walk_mods(&mut |id, module| {
+            for rule in &rules.forbidden_dependencies {
+                check_dependencies(
+                    &mut violations,
+                    "forbidden-dependencies",
+                    rule,
+                    |path| path,
+                    id,
+                    module,
+                );
+            }
+            for rule in &rules.forbidden_package_dependencies {
+                check_dependencies(
+                    &mut violations,
+                    "forbidden-package-dependencies",
+                    rule,
+                    package_of,
+                    id,
+                    module,
+                );
+            }
+        });
+
+        for crate_ in &package.crates {
+            let crate_id = format!("{}::{}", package.name, crate_.name);
+            for rule in &rules.public_root_items {
+                if rule.crate_.matches(&crate_id) {
+                    check_public_root_items(&mut violations, rule, &crate_id, crate_);
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+// Checks the dependencies of `module`, whose ID is `id`, matching the part of
+// IDs and dependencies that `key` returns.
+fn check_dependencies(
+    violations: &mut Vec<Violation>,
+    rule_name: &'static str,
+    rule: &ForbiddenDependency,
+    key: impl Fn(&str) -> &str,
+    id: &str,
+    module: &Mod,
+) {
+    if !rule.from.matches(key(id)) {
+        return;
+    }
+
+    let mut violation = |dep: &str, evidence: String| {
+        violations.push(Violation {
+            rule: rule_name,
+            message: format!(
+                "`{id}` depends on `{dep}`, but `{}` must not depend on `{}`",
+                rule.from, rule.to
+            ),
+            evidence: Some(evidence),
+        })
+    };
+    let mut imported = vec![];
+    for use_ in &module.items.uses {
+        for dep in use_.deps.iter().filter(|dep| rule.to.matches(key(dep))) {
+            violation(dep, use_.repr.clone());
+            imported.push(dep.as_str());
+        }
+    }
+    // Paths through imports, e.g., `Db::connect` after `use crate::infra::Db`,
+    // are reported with the imports.
+    let is_imported = |path: &str| {
+        imported.iter().any(|dep| {
+            path.strip_prefix(dep).is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+    };
+    for path in module.paths.iter().filter(|path| rule.to.matches(key(path))) {
+        if !is_imported(path) {
+            violation(path, format!("path `{path}`"));
+        }
+    }
+}
+
+// The package name in an ID or a dependency, e.g., `app` in `app::lib::Db`.
+fn package_of(path: &str) -> &str {
+    path.split_once("::").map_or(path, |(package, _)| package)
+}
+
+fn check_public_root_items(
+    violations: &mut Vec<Violation>,
+    rule: &PublicRootItems,
+    crate_id: &str,
+    root: &Mod,
+) {
+    let items = &root.items;
+    let mut public = vec![];
+
+    public.extend(
+        items
+            .mods
+            .iter()
+            .filter(|module| module.vis == Visibility::Public)
+            .map(|module| ("mod", module.name.clone(), None)),
+    );
+    public.extend(
+        items
+            .named_items()
+            .into_iter()
+            .filter(|item| item.vis == Visibility::Public)
+            .map(|item| (item.kind, item.name.to_owned(), Some(item.repr.to_owned()))),
+    );
+    for use_ in items.uses.iter().filter(|use_| use_.vis == Visibility::Public) {
+        public.extend(
+            syn_util::reexported_names(&use_.repr)
+                .map(|name| ("use", name, Some(use_.repr.clone()))),
+        );
+    }
+
+    for (kind, name, evidence) in public {
+        if !rule.allow.iter().any(|allowed| allowed.matches(&name)) {
+            let allow = rule.allow.iter().map(|allowed| format!("`{allowed}`")).collect::<Vec<_>>();
+            violations.push(Violation {
+                rule: "public-root-items",
+                message: format!(
+                    "{kind} `{crate_id}::{name}` is public at the crate root, but only {} may be",
+                    if allow.is_empty() { "nothing".to_owned() } else { allow.join(", ") },
+                ),
+                evidence,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Fn, Use};
+
+    #[test]
+    fn check_rules() {
+        let rules = toml::from_str::<RulesFile>(
+            r#"
+            [[rules.forbidden-dependencies]]
+            from = "app::lib::domain"
+            to = "app::lib::infra"
+
+            [[rules.forbidden-package-dependencies]]
+            from = "app"
+            to = "web"
+
+            [[rules.public-root-items]]
+            crate = "app::lib"
+            allow = ["api"]
+            "#,
+        )
+        .unwrap()
+        .rules;
+
+        let mut domain = Mod::new("domain");
+        domain.items.uses.push(Use {
+            vis: Visibility::Private,
+            repr: "use crate::infra::Db;".to_owned(),
+            deps: vec!["app::lib::infra::Db".to_owned()],
+        });
+        let mut api = Mod::new("api");
+        api.vis = Visibility::Public;
+        api.items.uses.push(Use {
+            vis: Visibility::Private,
+            repr: "use web::Router;".to_owned(),
+            deps: vec!["web::lib::Router".to_owned()],
+        });
+
+        let mut lib = Mod::new("lib");
+        lib.items.mods.extend([domain, api]);
+        lib.items.fns.push(Fn {
+            name: "helper".to_owned(),
+            vis: Visibility::Public,
+            repr: "fn helper() {}".to_owned(),
+            lines: 1,
+            complexity: 1,
+            ..Default::default()
+        });
+        lib.items.uses.push(Use {
+            vis: Visibility::Public,
+            repr: "pub use api::{Client, Server};".to_owned(),
+            deps: vec![],
+        });
+        let packages = vec![Package { name: "app".to_owned(), crates: vec![lib] }];
+
+        let violations = check(&rules, &packages)
+            .into_iter()
+            .map(|violation| (violation.rule, violation.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            violations,
+            vec![
+                (
+                    "forbidden-dependencies",
+                    "`app::lib::domain` depends on `app::lib::infra::Db`, but `app::lib::domain` \
+                     must not depend on `app::lib::infra`"
+                        .to_owned()
+                ),
+                (
+                    "forbidden-package-dependencies",
+                    "`app::lib::api` depends on `web::lib::Router`, but `app` must not depend on \
+                     `web`"
+                        .to_owned()
+                ),
+                (
+                    "public-root-items",
+                    "fn `app::lib::helper` is public at the crate root, but only `api` may be"
+                        .to_owned()
+                ),
+                (
+                    "public-root-items",
+                    "use `app::lib::Client` is public at the crate root, but only `api` may be"
+                        .to_owned()
+                ),
+                (
+                    "public-root-items",
+                    "use `app::lib::Server` is public at the crate root, but only `api` may be"
+                        .to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_super_imports() {
+        use crate::traverser::{self, Cache};
+
+        let proj = std::env::temp_dir().join(format!("rules-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("src/lib.rs", "pub mod domain;\npub mod infra;\n"),
+            ("src/domain.rs", "use super::infra::Db;\n"),
+            ("src/infra.rs", "pub struct Db;\n"),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let options = traverser::Options {
+            proj: proj.to_str().unwrap().to_owned(),
+            enable_edges: true,
+            ..Default::default()
+        };
+        let packages = traverser::traverse(&options, &Cache::default()).map(Iterator::collect);
+        std::fs::remove_dir_all(&proj).unwrap();
+        let packages: Vec<_> = packages.unwrap();
+
+        let rules = Rules {
+            forbidden_dependencies: vec![ForbiddenDependency {
+                from: "app::lib::domain".parse().unwrap(),
+                to: "app::lib::infra".parse().unwrap(),
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            check(&rules, &packages),
+            vec![Violation {
+                rule: "forbidden-dependencies",
+                message: "`app::lib::domain` depends on `app::lib::infra::Db`, but \
+                          `app::lib::domain` must not depend on `app::lib::infra`"
+                    .to_owned(),
+                evidence: Some("use super::infra::Db;".to_owned()),
+            }]
+        );
+    }
+
+    #[test]
+    fn check_code_paths() {
+        use crate::traverser::{self, Cache};
+
+        let proj = std::env::temp_dir().join(format!("rules-paths-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("src/lib.rs", "pub mod domain;\npub mod infra;\n"),
+            (
+                "src/domain.rs",
+                "use crate::infra::Pool;\n\npub fn run() {\n    Pool::new();\n    \
+                 crate::infra::Db::connect();\n}\n",
+            ),
+            ("src/infra.rs", "pub struct Db;\npub struct Pool;\n"),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let options = traverser::Options {
+            proj: proj.to_str().unwrap().to_owned(),
+            enable_edges: true,
+            ..Default::default()
+        };
+        let packages = traverser::traverse(&options, &Cache::default()).map(Iterator::collect);
+        std::fs::remove_dir_all(&proj).unwrap();
+        let packages: Vec<_> = packages.unwrap();
+
+        let rules = Rules {
+            forbidden_dependencies: vec![ForbiddenDependency {
+                from: "app::lib::domain".parse().unwrap(),
+                to: "app::lib::infra".parse().unwrap(),
+            }],
+            ..Default::default()
+        };
+        let violations = check(&rules, &packages)
+            .into_iter()
+            .map(|violation| violation.evidence.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(violations, ["use crate::infra::Pool;", "path `app::lib::infra::Db::connect`"]);
+    }
+}
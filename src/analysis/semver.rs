@@ -0,0 +1,487 @@
+//! Classification of public API changes by their [semver] impact.
+//!
+//! The public API of a package is the public items of its library crate, named
+//! `lib`, that are reachable through public modules, including `pub use`
+//! re-exports. Comparing versions without any library crate is an error. Items
+//! are compared by their `repr`s, so this is a rough, syntactic approximation:
+//!  - Removing an item, or changing a function signature or a type
+//!    definition, is major.
+//!  - Adding an item, a public field to a struct that cannot be constructed
+//!    outside of its crate, a variant to a `#[non_exhaustive]` enum, or a
+//!    trait item with a default is minor.
+//!  - Anything else, e.g., a change of a private field of a struct that
+//!    already has private fields, is a patch.
+//!
+//! Documentation and other attributes are ignored, except
+//! `#[non_exhaustive]`.
+//!
+//! [semver]: https://doc.rust-lang.org/cargo/reference/semver.html
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use displaydoc::Display;
+use quote::ToTokens;
+
+use crate::{
+    ir::{id, Mod, Package, Visibility},
+    syn_util,
+};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Display)]
+pub enum Level {
+    /// patch
+    Patch,
+    /// minor
+    Minor,
+    /// major
+    Major,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Change {
+    pub level: Level,
+    /// The item kind, e.g., `fn`, or `mod` or `use`.
+    pub kind: &'static str,
+    /// The ID of the item, as in the graph outputs. Re-exports have IDs of the
+    /// `use` kind, e.g., `pkg::lib::Error#use`.
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct Report {
+    /// The highest level of all changes, or `patch` if there are none.
+    pub level: Level,
+    /// Sorted by ID.
+    pub changes: Vec<Change>,
+}
+
+pub fn compare(old: &[Package], new: &[Package]) -> anyhow::Result<Report> {
+    let (old, new) = (public_api(old)?, public_api(new)?);
+    // Removed and added items by their paths, to tell changes of kind, e.g.,
+    // of a struct into an enum.
+    let kinds_by_path = |api: &BTreeMap<String, (&'static str, &str)>, other: &BTreeMap<_, _>| {
+        api.iter()
+            .filter(|(id, _)| !other.contains_key(*id))
+            .map(|(id, &(kind, _))| (id::path(id).to_owned(), kind))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let (removed, added) = (kinds_by_path(&old, &new), kinds_by_path(&new, &old));
+    let mut changes = vec![];
+
+    for (id, &(kind, old_repr)) in &old {
+        let mut push =
+            |level, reason: String| changes.push(Change { level, kind, id: id.clone(), reason });
+        match new.get(id) {
+            None => match added.get(id::path(id)) {
+                Some(new_kind) => push(Level::Major, format!("changed from {kind} to {new_kind}")),
+                None => push(Level::Major, "removed".to_owned()),
+            },
+            Some(&(_, new_repr)) if new_repr != old_repr => {
+                for (level, reason) in classify(kind, old_repr, new_repr) {
+                    push(level, reason);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    for (id, &(kind, _)) in &new {
+        if !old.contains_key(id) && !removed.contains_key(id::path(id)) {
+            changes.push(Change {
+                level: Level::Minor,
+                kind,
+                id: id.clone(),
+                reason: "added".to_owned(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let level = changes.iter().map(|change| change.level).max().unwrap_or(Level::Patch);
+    Ok(Report { level, changes })
+}
+
+/// Renders a report as human-readable text.
+pub fn report(report: &Report) -> String {
+    let mut text = String::new();
+    for change in &report.changes {
+        text.push_str(&format!(
+            "{:<5}  {} `{}`: {}\n",
+            change.level,
+            change.kind,
+            id::path(&change.id),
+            change.reason
+        ));
+    }
+    if report.changes.is_empty() {
+        text.push_str("No public API changes found.\n");
+    }
+    text.push_str(&format!("\nRequired version bump: {}.\n", report.level));
+    text
+}
+
+// Public item kinds and `repr`s by IDs.
+fn public_api(packages: &[Package]) -> anyhow::Result<BTreeMap<String, (&'static str, &str)>> {
+    let mut api = BTreeMap::new();
+    let mut libs = 0;
+    for package in packages {
+        for crate_ in package.crates.iter().filter(|crate_| crate_.name == id::LIB) {
+            collect_public_items(&mut api, &format!("{}::{}", package.name, id::LIB), crate_);
+            libs += 1;
+        }
+    }
+    if libs == 0 {
+        bail!(
+            "No library crate named `{}` found; name it in a crate roots list with a \
+             `package::{}=` prefix",
+            id::LIB,
+            id::LIB
+        );
+    }
+    Ok(api)
+}
+
+fn collect_public_items<'a>(
+    api: &mut BTreeMap<String, (&'static str, &'a str)>,
+    id: &str,
+    module: &'a Mod,
+) {
+    let items = &module.items;
+    for item in items.named_items().into_iter().filter(|item| item.vis == Visibility::Public) {
+        api.insert(item.id(id), (item.kind, item.repr));
+    }
+    for use_ in items.uses.iter().filter(|use_| use_.vis == Visibility::Public) {
+        for name in syn_util::reexported_names(&use_.repr) {
+            api.insert(id::item(id, "use", &name), ("use", &use_.repr));
+        }
+    }
+    for module in items.mods.iter().filter(|module| module.vis == Visibility::Public) {
+        let id = format!("{id}::{}", module.name);
+        api.insert(id.clone(), ("mod", ""));
+        collect_public_items(api, &id, module);
+    }
+}
+
+// Classifies the changes of an item whose `repr` has changed. Returns nothing
+// if only documentation or other attributes have changed.
+fn classify(kind: &str, old: &str, new: &str) -> Vec<(Level, String)> {
+    let changes = match kind {
+        "enum" => parse_both(old, new).map(|(old, new)| classify_enum(&old, &new)),
+        "struct" => parse_both(old, new).map(|(old, new)| classify_struct(&old, &new)),
+        "trait" => parse_both(old, new).map(|(old, new)| classify_trait(&old, &new)),
+        // A re-export of something else under the same name.
+        "use" => Some(vec![(Level::Major, "re-exports another path".to_owned())]),
+        "fn" => Some(vec![(Level::Major, "signature changed".to_owned())]),
+        _ => None,
+    };
+    changes.unwrap_or_else(|| vec![(Level::Major, "definition changed".to_owned())])
+}
+
+fn parse_both<T: syn::parse::Parse>(old: &str, new: &str) -> Option<(T, T)> {
+    Some((syn::parse_str(old).ok()?, syn::parse_str(new).ok()?))
+}
+
+fn classify_enum(old: &syn::ItemEnum, new: &syn::ItemEnum) -> Vec<(Level, String)> {
+    let mut changes = classify_header(&old.generics, &new.generics, &old.attrs, &new.attrs);
+
+    let variants = |item: &syn::ItemEnum| {
+        item.variants
+            .iter()
+            .map(|variant| {
+                let variant = syn::Variant { attrs: vec![], ..variant.clone() };
+                (variant.ident.to_string(), tokens(variant))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    let (old_variants, new_variants) = (variants(old), variants(new));
+    let added = if is_non_exhaustive(&old.attrs) { Level::Minor } else { Level::Major };
+    changes.extend(compare_members("variant", &old_variants, &new_variants, |_| added));
+    changes
+}
+
+fn classify_struct(old: &syn::ItemStruct, new: &syn::ItemStruct) -> Vec<(Level, String)> {
+    let mut changes = classify_header(&old.generics, &new.generics, &old.attrs, &new.attrs);
+
+    // Public fields by names or positions, and private fields without
+    // attributes.
+    let fields = |item: &syn::ItemStruct| {
+        let mut public = BTreeMap::new();
+        let mut private = vec![];
+        for (i, field) in item.fields.iter().enumerate() {
+            if matches!(field.vis, syn::Visibility::Public(_)) {
+                let name = field.ident.as_ref().map_or_else(|| i.to_string(), ToString::to_string);
+                public.insert(name, tokens(&field.ty));
+            } else {
+                private.push(tokens(syn::Field { attrs: vec![], ..field.clone() }));
+            }
+        }
+        (public, private)
+    };
+    let ((old_public, old_private), (new_public, new_private)) = (fields(old), fields(new));
+
+    // Structs with only public fields can be constructed by other crates.
+    let constructible = old_private.is_empty() && !is_non_exhaustive(&old.attrs);
+    let added = if constructible { Level::Major } else { Level::Minor };
+    changes.extend(compare_members("field", &old_public, &new_public, |_| added));
+    if constructible && !new_private.is_empty() {
+        changes.push((Level::Major, "private fields added".to_owned()));
+    }
+    if matches!(old.fields, syn::Fields::Named(_)) != matches!(new.fields, syn::Fields::Named(_)) {
+        changes.push((Level::Major, "field style changed".to_owned()));
+    }
+    if changes.is_empty() && old_private != new_private {
+        changes.push((Level::Patch, "changed privately".to_owned()));
+    }
+    changes
+}
+
+fn classify_trait(old: &syn::ItemTrait, new: &syn::ItemTrait) -> Vec<(Level, String)> {
+    let mut changes = classify_header(&old.generics, &new.generics, &old.attrs, &new.attrs);
+    if tokens(&old.supertraits) != tokens(&new.supertraits) {
+        changes.push((Level::Major, "supertraits changed".to_owned()));
+    }
+    if old.unsafety.is_some() != new.unsafety.is_some() {
+        changes.push((Level::Major, "safety changed".to_owned()));
+    }
+
+    // Trait items by names, without default bodies, paired with whether they
+    // have defaults.
+    let items = |item: &syn::ItemTrait| {
+        item.items
+            .iter()
+            .filter_map(|item| match item {
+                syn::TraitItem::Fn(item) => {
+                    Some((item.sig.ident.to_string(), (tokens(&item.sig), item.default.is_some())))
+                }
+                syn::TraitItem::Type(item) => Some((
+                    item.ident.to_string(),
+                    (
+                        tokens(syn::TraitItemType { attrs: vec![], default: None, ..item.clone() }),
+                        item.default.is_some(),
+                    ),
+                )),
+                syn::TraitItem::Const(item) => Some((
+                    item.ident.to_string(),
+                    (
+                        tokens(syn::TraitItemConst {
+                            attrs: vec![],
+                            default: None,
+                            ..item.clone()
+                        }),
+                        item.default.is_some(),
+                    ),
+                )),
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    let (old_items, new_items) = (items(old), items(new));
+    let signatures = |items: &BTreeMap<String, (String, bool)>| {
+        items.iter().map(|(name, (signature, _))| (name.clone(), signature.clone())).collect()
+    };
+    changes.extend(compare_members(
+        "item",
+        &signatures(&old_items),
+        &signatures(&new_items),
+        |name| if new_items[name].1 { Level::Minor } else { Level::Major },
+    ));
+    changes
+}
+
+// Compares generics and attributes, shared by all type definitions.
+fn classify_header(
+    old_generics: &syn::Generics,
+    new_generics: &syn::Generics,
+    old_attrs: &[syn::Attribute],
+    new_attrs: &[syn::Attribute],
+) -> Vec<(Level, String)> {
+    let mut changes = vec![];
+    if tokens(old_generics) != tokens(new_generics)
+        || tokens(&old_generics.where_clause) != tokens(&new_generics.where_clause)
+    {
+        changes.push((Level::Major, "generics changed".to_owned()));
+    }
+    if !is_non_exhaustive(old_attrs) && is_non_exhaustive(new_attrs) {
+        changes.push((Level::Major, "became `#[non_exhaustive]`".to_owned()));
+    }
+    changes
+}
+
+// Compares named members, e.g., enum variants: removing or changing a member
+// is major, and adding one is up to `added`.
+fn compare_members(
+    member: &str,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    added: impl Fn(&str) -> Level,
+) -> Vec<(Level, String)> {
+    let mut changes = vec![];
+    for (name, old) in old {
+        match new.get(name) {
+            None => changes.push((Level::Major, format!("{member} `{name}` removed"))),
+            Some(new) if new != old => {
+                changes.push((Level::Major, format!("{member} `{name}` changed")))
+            }
+            Some(_) => {}
+        }
+    }
+    for name in new.keys().filter(|name| !old.contains_key(*name)) {
+        changes.push((added(name), format!("{member} `{name}` added")));
+    }
+    changes
+}
+
+fn is_non_exhaustive(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("non_exhaustive"))
+}
+
+fn tokens(node: impl ToTokens) -> String {
+    node.to_token_stream().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Enum, Fn, Struct, Trait};
+
+    fn package(fns: &[&str], enums: &[&str], structs: &[&str], traits: &[&str]) -> Vec<Package> {
+        let name = |repr: &str| {
+            let item = syn::parse_str::<syn::Item>(repr).unwrap();
+            match item {
+                syn::Item::Fn(item) => item.sig.ident.to_string(),
+                syn::Item::Enum(item) => item.ident.to_string(),
+                syn::Item::Struct(item) => item.ident.to_string(),
+                syn::Item::Trait(item) => item.ident.to_string(),
+                _ => unreachable!(),
+            }
+        };
+        let mut lib = Mod::new(id::LIB);
+        for repr in fns {
+            lib.items.fns.push(Fn {
+                name: name(repr),
+                vis: Visibility::Public,
+                repr: repr.to_string(),
+                lines: 1,
+                complexity: 1,
+                ..Default::default()
+            });
+        }
+        for repr in enums {
+            let (name, vis, repr) = (name(repr), Visibility::Public, repr.to_string());
+            lib.items.enums.push(Enum { name, vis, repr, ..Default::default() });
+        }
+        for repr in structs {
+            let (name, vis, repr) = (name(repr), Visibility::Public, repr.to_string());
+            lib.items.structs.push(Struct { name, vis, repr, ..Default::default() });
+        }
+        for repr in traits {
+            let (name, vis, repr) = (name(repr), Visibility::Public, repr.to_string());
+            lib.items.traits.push(Trait { name, vis, repr, ..Default::default() });
+        }
+        vec![Package { name: "pkg".to_owned(), crates: vec![lib] }]
+    }
+
+    fn changes(report: &Report) -> Vec<(Level, &str, &str)> {
+        report
+            .changes
+            .iter()
+            .map(|change| (change.level, change.id.as_str(), change.reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn classify_changes() {
+        let old = package(
+            &["pub fn run(x: i32) {}", "pub fn stop() {}"],
+            &["pub enum Open { A }", "#[non_exhaustive]\npub enum Closed { A }"],
+            &["pub struct Config { pub a: i32, b: i32 }"],
+            &["pub trait Service { fn call(&self); }"],
+        );
+        let new = package(
+            &["pub fn run(x: u32) {}", "pub fn start() {}"],
+            &["pub enum Open { A, B }", "#[non_exhaustive]\npub enum Closed { A, B }"],
+            &["pub struct Config { pub a: i32, b: u32, pub c: i32 }"],
+            &["pub trait Service { fn call(&self); fn ready(&self) -> bool { true } }"],
+        );
+
+        let report = compare(&old, &new).unwrap();
+        assert_eq!(
+            changes(&report),
+            vec![
+                (Level::Minor, "pkg::lib::Closed#enum", "variant `B` added"),
+                (Level::Minor, "pkg::lib::Config#struct", "field `c` added"),
+                (Level::Major, "pkg::lib::Open#enum", "variant `B` added"),
+                (Level::Minor, "pkg::lib::Service#trait", "item `ready` added"),
+                (Level::Major, "pkg::lib::run#fn", "signature changed"),
+                (Level::Minor, "pkg::lib::start#fn", "added"),
+                (Level::Major, "pkg::lib::stop#fn", "removed"),
+            ]
+        );
+        assert_eq!(report.level, Level::Major);
+
+        let old = package(&[], &[], &["pub struct Config { pub a: i32, b: i32 }"], &[]);
+        let new = package(&[], &[], &["pub struct Config { pub a: i32, b: u32 }"], &[]);
+        let report = compare(&old, &new).unwrap();
+        assert_eq!(
+            changes(&report),
+            vec![(Level::Patch, "pkg::lib::Config#struct", "changed privately")]
+        );
+        assert_eq!(report.level, Level::Patch);
+    }
+
+    #[test]
+    fn same_names_in_different_namespaces() {
+        // A trait and a function may share a name, and both may change.
+        let old = package(
+            &["pub fn Service() {}"],
+            &[],
+            &["pub struct Config;"],
+            &["pub trait Service {}"],
+        );
+        let new = package(
+            &["pub fn Service(x: i32) {}"],
+            &["pub enum Config {}"],
+            &[],
+            &["pub trait Service { fn call(&self); }"],
+        );
+
+        let report = compare(&old, &new).unwrap();
+        assert_eq!(
+            changes(&report),
+            vec![
+                (Level::Major, "pkg::lib::Config#struct", "changed from struct to enum"),
+                (Level::Major, "pkg::lib::Service#fn", "signature changed"),
+                (Level::Major, "pkg::lib::Service#trait", "item `call` added"),
+            ]
+        );
+        assert!(super::report(&report).contains("struct `pkg::lib::Config`: changed"));
+    }
+
+    #[test]
+    fn ignore_docs() {
+        let old = package(
+            &[],
+            &["pub enum Kind {\n    /// A.\n    A,\n}"],
+            &["pub struct Config {\n    /// A.\n    pub a: i32,\n    b: i32,\n}"],
+            &["pub trait Service {\n    /// An output.\n    type Output;\n    const N: usize;\n}"],
+        );
+        let new = package(
+            &[],
+            &["pub enum Kind {\n    /// The first kind.\n    A,\n}"],
+            &["pub struct Config {\n    pub a: i32,\n    /// B.\n    b: i32,\n}"],
+            &["pub trait Service {\n    type Output;\n    /// A number.\n    const N: usize;\n}"],
+        );
+
+        let report = compare(&old, &new).unwrap();
+        assert_eq!(changes(&report), vec![]);
+        assert_eq!(report.level, Level::Patch);
+    }
+
+    #[test]
+    fn no_library() {
+        let mut packages = package(&["pub fn run() {}"], &[], &[], &[]);
+        packages[0].crates[0].name = "main".to_owned();
+        assert!(compare(&packages, &packages).is_err());
+    }
+}
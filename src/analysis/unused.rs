@@ -0,0 +1,180 @@
+//! Detection of `pub` and `pub(crate)` items that no other module uses.
+//!
+//! References are taken from `use` statements and paths in code anywhere in
+//! the workspace, as resolved by the traverser. Names brought into scope by
+//! `use` are not tracked further, so a `use` of a module counts as a
+//! reference to every item in it, and the results are only candidates for
+//! narrowing visibility or deletion. References within the defining module do
+//! not count, since they need no visibility at all.
+//!
+//! Public items at the roots of library crates, and items re-exported from
+//! there, are intentional API and never reported.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::{id, Package, Visibility};
+
+/// An item that nothing outside its module uses.
+#[derive(Debug, Eq, PartialEq)]
+pub struct UnusedItem {
+    pub module: String,
+    /// The item kind, e.g., `fn`.
+    pub kind: &'static str,
+    pub name: String,
+    pub vis: Visibility,
+}
+
+/// Finds unused items, in the order of traversal.
+pub fn find(packages: &[Package]) -> Vec<UnusedItem> {
+    // Use paths, covering everything inside them, and paths in code with all
+    // their prefixes, paired with the referencing modules.
+    let mut use_references = HashMap::<&str, HashSet<String>>::new();
+    let mut path_references = HashMap::<&str, HashSet<String>>::new();
+    // Paths that are intentional API.
+    let mut exempt = HashSet::new();
+    for package in packages {
+        let lib_id = format!("{}::{}", package.name, id::LIB);
+        package.walk_mods(&mut |id, module| {
+            for use_ in &module.items.uses {
+                for path in &use_.deps {
+                    if id == lib_id && use_.vis == Visibility::Public {
+                        exempt.insert(path.as_str());
+                    }
+                    use_references.entry(path.as_str()).or_default().insert(id.to_owned());
+                }
+            }
+            for path in &module.paths {
+                for prefix in id::ancestors(path) {
+                    path_references.entry(prefix).or_default().insert(id.to_owned());
+                }
+            }
+        });
+    }
+
+    let mut unused = vec![];
+    for package in packages {
+        let lib_id = format!("{}::{}", package.name, id::LIB);
+        package.walk_mods(&mut |id, module| {
+            for item in module.items.named_items() {
+                if item.vis == Visibility::Private
+                    || (id == lib_id && item.vis == Visibility::Public)
+                {
+                    continue;
+                }
+                let item_path = item.path(id);
+                let is_referenced = |references: &HashMap<&str, HashSet<String>>, path: &str| {
+                    references
+                        .get(path)
+                        .is_some_and(|modules| modules.iter().any(|module| module != id))
+                };
+                let is_used = is_referenced(&path_references, &item_path)
+                    || id::ancestors(&item_path)
+                        .any(|path| exempt.contains(path) || is_referenced(&use_references, path));
+                if !is_used {
+                    unused.push(UnusedItem {
+                        module: id.to_owned(),
+                        kind: item.kind,
+                        name: item.name.to_owned(),
+                        vis: item.vis,
+                    });
+                }
+            }
+        });
+    }
+    unused
+}
+
+/// Renders unused items as a human-readable report, grouped by module.
+pub fn report(unused: &[UnusedItem]) -> String {
+    if unused.is_empty() {
+        return "No unused public items found.\n".to_owned();
+    }
+
+    let mut report = String::new();
+    let mut current_module = None;
+    for item in unused {
+        if current_module != Some(&item.module) {
+            report.push_str(&format!("{}:\n", item.module));
+            current_module = Some(&item.module);
+        }
+        let vis = match item.vis {
+            Visibility::Public => "pub",
+            _ => "pub(restricted)",
+        };
+        report.push_str(&format!("  {vis} {} {}\n", item.kind, item.name));
+    }
+    report.push_str(&format!("\n{} unused public item(s).\n", unused.len()));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Fn, Mod, Struct, Use};
+
+    fn item_fn(name: &str, vis: Visibility) -> Fn {
+        Fn {
+            name: name.to_owned(),
+            vis,
+            repr: String::new(),
+            lines: 1,
+            complexity: 1,
+            ..Default::default()
+        }
+    }
+
+    fn item_use(repr: &str, dep: &str, vis: Visibility) -> Use {
+        Use { vis, repr: repr.to_owned(), deps: vec![dep.to_owned()] }
+    }
+
+    #[test]
+    fn find_unused() {
+        let mut db = Mod::new("db");
+        db.items.fns.push(item_fn("connect", Visibility::Restricted));
+        db.items.fns.push(item_fn("migrate", Visibility::Restricted));
+        db.items.fns.push(item_fn("pending", Visibility::Restricted));
+        db.items.fns.push(item_fn("helper", Visibility::Private));
+        db.items.structs.push(Struct {
+            name: "Pool".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        let mut api = Mod::new("api");
+        api.items.fns.push(item_fn("serve", Visibility::Public));
+        api.items.fns.push(item_fn("unused", Visibility::Public));
+        api.items.uses.push(item_use(
+            "use super::db::connect;",
+            "app::lib::db::connect",
+            Visibility::Private,
+        ));
+
+        let mut lib = Mod::new("lib");
+        lib.items.mods.extend([db, api]);
+        lib.items.fns.push(item_fn("run", Visibility::Public));
+        lib.items.uses.push(item_use(
+            "pub use self::db::Pool;",
+            "app::lib::db::Pool",
+            Visibility::Public,
+        ));
+        let mut main = Mod::new("main");
+        main.items.uses.push(item_use(
+            "use app::api::{serve as start};",
+            "app::lib::api::serve",
+            Visibility::Private,
+        ));
+        main.paths.push("app::lib::db::migrate".to_owned());
+        let packages = vec![Package { name: "app".to_owned(), crates: vec![lib, main] }];
+
+        let unused = find(&packages);
+        assert_eq!(
+            unused.iter().map(|item| format!("{}::{}", item.module, item.name)).collect::<Vec<_>>(),
+            vec!["app::lib::db::pending", "app::lib::api::unused"]
+        );
+        assert_eq!(
+            report(&unused),
+            "app::lib::db:\n  pub(restricted) fn pending\napp::lib::api:\n  pub fn unused\n\n2 \
+             unused public item(s).\n"
+        );
+    }
+}
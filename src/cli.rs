@@ -1,18 +1,328 @@
-use clap::Parser;
+//! The command-line interface of the `rust-ontologist` binary.
+//!
+//! [`run`] parses the command line, with the defaults from the configuration
+//! file, and runs the command. Tools shipping their own output backends call
+//! it with their [`Registry`], so that `--format` and `--output` offer their
+//! formats next to the built-in ones:
+//!
+//! ```no_run
+//! use rust_ontologist::{cli, output::Registry};
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let registry = Registry::default();
+//!     // registry.register(MyBackend);
+//!     cli::run(registry)
+//! }
+//! ```
+
+mod commands;
+mod config;
+mod server;
+mod watcher;
+
+use anyhow::{bail, Context};
+use clap::{
+    builder::{PossibleValue, PossibleValuesParser},
+    CommandFactory, FromArgMatches, Parser, Subcommand,
+};
+
+use crate::{
+    analysis,
+    filter::Filter,
+    output::{self, Registry},
+    pattern::PathPattern,
+    traverser,
+};
+
+/// Runs the command given on the command line, with the output formats of
+/// `registry`. Also initializes the logger from `RUST_LOG`.
+pub fn run(registry: Registry) -> anyhow::Result<()> {
+    let matches =
+        Args::command().mut_arg("format", |arg| arg.value_parser(formats(&registry))).get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    pretty_env_logger::init();
+    let config = config::load(&args, &registry)?;
+    if let Some(config) = &config {
+        config::merge(config, &mut args, &matches);
+    }
+    if args.focus.is_some() && !args.enable_edges {
+        bail!("`--focus` needs `--enable-edges`.");
+    }
+
+    match &args.command {
+        Some(Command::Check { rules }) => {
+            let rules = match rules {
+                Some(path) => analysis::rules::load(path)?,
+                None => config
+                    .map(|config| config.rules)
+                    .with_context(|| format!("No ontologist.toml found in {}", args.proj))?,
+            };
+            commands::check(&Args { enable_edges: true, ..args }, &rules)
+        }
+        Some(Command::Metrics { json }) => {
+            let json = *json;
+            commands::metrics(&Args { enable_edges: true, ..args }, json)
+        }
+        Some(Command::Orphans) => commands::orphans(&args),
+        Some(Command::Unused) => commands::unused(&Args { enable_edges: true, ..args }),
+        Some(Command::Query { query, json }) => {
+            let (query, json) = (query.join(" "), *json);
+            commands::query(&Args { enable_edges: true, ..args }, &query, json)
+        }
+        Some(Command::Cycles) => commands::cycles(&Args { enable_edges: true, ..args }),
+        Some(Command::Diff { old, new }) => {
+            let (old, new) = (old.clone(), new.clone());
+            commands::diff(&Args { enable_edges: true, ..args }, &registry, &old, &new)
+        }
+        Some(Command::Semver { old, new }) => commands::semver(&args, old, new),
+        Some(Command::Serve { port }) => commands::serve(&args, *port),
+        None => commands::dump(&args, &registry),
+    }
+}
 
 /// A Rust codebase visualizer.
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// The cargo project root directory.
     #[arg(short, long)]
     pub proj: String,
 
-    /// The name of the output dump file.
+    /// A file listing crate root files to traverse instead of the Cargo
+    /// workspace, or `-` for stdin. See `src/roots.rs` for the format.
+    #[arg(long)]
+    pub crate_roots: Option<String>,
+
+    /// A rust-analyzer `rust-project.json` file describing the crates to
+    /// traverse instead of the Cargo workspace.
+    #[arg(long, conflicts_with = "crate_roots")]
+    pub rust_project: Option<String>,
+
+    /// The configuration file [default: `<PROJ>/ontologist.toml`, if any].
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// The name of the output dump file (a directory for `neo4j`), or `-` for
+    /// stdout, optionally prefixed with its format, e.g., `ir=ir.json`. Repeat
+    /// to write several outputs from a single traversal.
     #[arg(short, long, default_value = "codebase-dump.json")]
-    pub output: String,
+    pub output: Vec<String>,
+
+    /// The format of outputs without a format prefix.
+    #[arg(short, long, value_parser = formats(&Registry::default()), default_value = "cytoscape")]
+    pub format: String,
+
+    /// A directory with the JavaScript dependencies for `html`, overriding the
+    /// ones embedded at build time.
+    #[arg(long)]
+    pub assets: Option<String>,
+
+    /// CSS colours for the dependency edges of successive crates, instead of
+    /// the built-in ones. Repeat to give several.
+    #[arg(long)]
+    pub palette: Vec<String>,
 
     /// Enable edges in the output dump (experimental).
     #[arg(long, default_value = "false")]
     pub enable_edges: bool,
+
+    /// Extract items at least this visible.
+    #[arg(long, value_parser = VISIBILITIES, default_value = "restricted")]
+    pub visibility: String,
+
+    /// Only extract items enabled by these `cfg` options, e.g., `unix` or
+    /// `feature="serde"`, instead of all `#[cfg]` alternatives.
+    #[arg(long)]
+    pub cfg: Vec<String>,
+
+    /// Only extract items enabled by these features, like `--cfg
+    /// 'feature="<FEATURE>"'`. Default features are not implied.
+    #[arg(long, value_delimiter = ',')]
+    pub features: Vec<String>,
+
+    /// A directory to persist parsed files in, to speed up subsequent runs.
+    #[arg(long)]
+    pub cache_dir: Option<String>,
+
+    /// Add commit counts, authors, and last-modified dates of modules from the
+    /// local git repository.
+    #[arg(long, default_value = "false")]
+    pub history: bool,
+
+    /// How far back to read the git history, in any format accepted by
+    /// `git log --since`.
+    #[arg(long, default_value = "1 year ago")]
+    pub history_since: String,
+
+    /// Only output the module with this ID, e.g., `pkg::lib::foo`, and its
+    /// descendants.
+    #[arg(long)]
+    pub root: Option<String>,
+
+    /// Only output modules nested at most this deep below crate roots.
+    #[arg(long)]
+    pub max_depth: Option<usize>,
+
+    /// Only output modules and items matching one of these patterns, e.g.,
+    /// `pkg::lib::*::db`.
+    #[arg(long)]
+    pub include: Vec<PathPattern>,
+
+    /// Do not output modules and items matching any of these patterns, e.g.,
+    /// `**::tests`.
+    #[arg(long)]
+    pub exclude: Vec<PathPattern>,
+
+    /// Only output items of these kinds.
+    #[arg(long, value_parser = KINDS)]
+    pub kind: Vec<String>,
+
+    /// Only output the modules and items within `--hops` dependencies of this
+    /// module or item. Needs `--enable-edges`.
+    #[arg(long)]
+    pub focus: Option<String>,
+
+    /// The number of dependency hops around `--focus` to output.
+    #[arg(long, default_value_t = 1)]
+    pub hops: usize,
+
+    /// Collapse modules this deep below crate roots, with all their contents,
+    /// into single vertices with weighted dependency edges.
+    #[arg(long)]
+    pub collapse_depth: Option<usize>,
+
+    /// Keep running and rewrite the output whenever the project changes.
+    #[arg(long, default_value = "false")]
+    pub watch: bool,
+}
+
+/// An output dump file and its format.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Output {
+    pub format: String,
+    pub path: String,
+}
+
+impl Args {
+    /// Returns the outputs given by `--output`, in `--format` unless prefixed
+    /// with another one.
+    pub fn outputs(&self, registry: &Registry) -> Vec<Output> {
+        self.output
+            .iter()
+            .map(|output| {
+                let prefixed = output.split_once('=').and_then(|(format, path)| {
+                    registry.get(format)?;
+                    Some(Output { format: format.to_owned(), path: path.to_owned() })
+                });
+                prefixed
+                    .unwrap_or_else(|| Output { format: self.format.clone(), path: output.clone() })
+            })
+            .collect()
+    }
+
+    pub fn output_options(&self) -> output::Options {
+        output::Options {
+            assets: self.assets.clone().map(Into::into),
+            palette: self.palette.clone(),
+        }
+    }
+
+    pub fn traversal(&self) -> traverser::Options {
+        traverser::Options {
+            proj: self.proj.clone(),
+            crate_roots: self.crate_roots.clone(),
+            rust_project: self.rust_project.clone(),
+            enable_edges: self.enable_edges,
+            history: self.history.then(|| self.history_since.clone()),
+            visibility: self.visibility.parse().expect("Must be validated by clap"),
+            cfg: (!self.cfg.is_empty() || !self.features.is_empty()).then(|| {
+                let features = self.features.iter().map(|feature| format!("feature={feature:?}"));
+                self.cfg.iter().cloned().chain(features).collect()
+            }),
+        }
+    }
+
+    pub fn filter(&self) -> Filter {
+        Filter {
+            root: self.root.clone(),
+            max_depth: self.max_depth,
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+            kinds: self.kind.clone(),
+            focus: self.focus.clone().map(|focus| (focus, self.hops)),
+        }
+    }
+}
+
+// The `--format` choices: the formats of the registered output backends.
+fn formats(registry: &Registry) -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        registry
+            .iter()
+            .map(|backend| PossibleValue::new(backend.name()).help(backend.description())),
+    )
+}
+
+/// The `--visibility` levels.
+pub const VISIBILITIES: [&str; 3] = ["public", "restricted", "private"];
+
+/// The item kinds, as in the outputs.
+pub const KINDS: [&str; 8] = ["const", "enum", "fn", "static", "struct", "trait", "type", "union"];
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Check architecture rules and fail if any of them is violated.
+    Check {
+        /// The file with the rules [default: the configuration file].
+        #[arg(long)]
+        rules: Option<String>,
+    },
+    /// Print coupling and stability metrics of modules and crates.
+    Metrics {
+        /// Print JSON instead of a table.
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+    /// Print `.rs` files under `src/` that no `mod` declaration reaches.
+    Orphans,
+    /// Print `pub` and `pub(crate)` items that no other module uses.
+    Unused,
+    /// Answer a query about the project, e.g., `deps-of crate::traverser`,
+    /// `rdeps-of ir::Mod`, `path-from a to b`, `items kind=trait in pkg::*`,
+    /// or `impls-of Serialize`.
+    Query {
+        /// The query, possibly split into several arguments.
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Print JSON instead of text.
+        #[arg(long, default_value = "false")]
+        json: bool,
+    },
+    /// Print module dependency cycles, with the `use` statements causing them.
+    Cycles,
+    /// Print the structural changes between two versions of the project and
+    /// write the new version, coloured by change status, to `--output`.
+    Diff {
+        /// The old version: an IR dump (`--format ir`) or a git revision.
+        old: String,
+        /// The new version: an IR dump (`--format ir`) or a git revision.
+        new: String,
+    },
+    /// Classify the public API changes between two versions of the project as
+    /// major, minor, or patch.
+    Semver {
+        /// The old version: an IR dump (`--format ir`) or a git revision.
+        old: String,
+        /// The new version: an IR dump (`--format ir`) or a git revision.
+        new: String,
+    },
+    /// Serve the viewer locally and reload it whenever the project changes.
+    Serve {
+        /// The port to listen on.
+        #[arg(long, default_value_t = 8000)]
+        port: u16,
+    },
 }
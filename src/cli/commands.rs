@@ -0,0 +1,244 @@
+//! The subcommands and the default dump of the command-line interface.
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+
+use super::{server::Server, watcher::Watcher};
+use crate::{
+    analysis, cli, git, ir,
+    output::{cytoscape, html, Registry},
+    traverser::{self, Cache},
+    Ontology, OntologyBuilder,
+};
+
+// How often the project is checked for changes in `--watch` and `serve`.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(super) fn dump(args: &cli::Args, registry: &Registry) -> anyhow::Result<()> {
+    let cache = Cache::new(args.cache_dir.as_ref().map(PathBuf::from));
+    write_dump(args, registry, &cache)?;
+
+    if args.watch {
+        watch(args, &cache, || write_dump(args, registry, &cache))?;
+    }
+    Ok(())
+}
+
+fn write_dump(args: &cli::Args, registry: &Registry, cache: &Cache) -> anyhow::Result<()> {
+    let packages = ontology(args).build_with(cache)?.into_packages();
+    for output in args.outputs(registry) {
+        let backend = registry.get(&output.format).expect("Must be a registered format");
+        if output.path == "-" {
+            backend.write(&packages, &args.output_options(), &mut std::io::stdout().lock())?;
+        } else {
+            backend.write_to_path(&packages, &args.output_options(), Path::new(&output.path))?;
+        }
+        log::info!("The codebase is successfully dumped to {}.", output.path);
+    }
+    Ok(())
+}
+
+// Writes to a file or, for `-`, to stdout.
+fn write_file(path: &str, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    if path == "-" {
+        std::io::stdout().write_all(contents.as_ref())?;
+    } else {
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+pub(super) fn check(args: &cli::Args, rules: &analysis::rules::Rules) -> anyhow::Result<()> {
+    let packages = traverse_private(args)?;
+    let violations = analysis::rules::check(rules, &packages);
+    for violation in &violations {
+        println!("{violation}");
+    }
+    if !violations.is_empty() {
+        bail!("Found {} architecture rule violation(s).", violations.len());
+    }
+    println!("All architecture rules are satisfied.");
+    Ok(())
+}
+
+pub(super) fn orphans(args: &cli::Args) -> anyhow::Result<()> {
+    let members = traverser::members(&args.traversal())?;
+    let orphans = analysis::orphans::find(&members, &traverse(args)?);
+    for orphan in &orphans {
+        println!("{}", orphan.strip_prefix(&args.proj).unwrap_or(orphan).display());
+    }
+    if orphans.is_empty() {
+        println!("No orphan source files found.");
+    }
+    Ok(())
+}
+
+pub(super) fn unused(args: &cli::Args) -> anyhow::Result<()> {
+    let packages = traverse_private(args)?;
+    let mut unused = analysis::unused::find(&packages);
+    unused.retain(|item| item.vis.is_at_least(args.traversal().visibility));
+    print!("{}", analysis::unused::report(&unused));
+    Ok(())
+}
+
+pub(super) fn query(args: &cli::Args, query: &str, json: bool) -> anyhow::Result<()> {
+    let query = query.parse::<analysis::query::Query>()?;
+    let answer = analysis::query::run(&query, &traverse(args)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&answer).expect("Failed to pretty-print JSON"));
+    } else {
+        print!("{}", analysis::query::render(&answer));
+    }
+    Ok(())
+}
+
+pub(super) fn cycles(args: &cli::Args) -> anyhow::Result<()> {
+    let packages = traverse_private(args)?;
+    print!("{}", analysis::cycles::report(&analysis::cycles::find(&packages)));
+    Ok(())
+}
+
+pub(super) fn diff(
+    args: &cli::Args,
+    registry: &Registry,
+    old: &str,
+    new: &str,
+) -> anyhow::Result<()> {
+    let outputs = args.outputs(registry);
+    if outputs.iter().any(|output| !["cytoscape", "html"].contains(&output.format.as_str())) {
+        bail!("`diff` can only write `cytoscape` and `html` outputs.");
+    }
+
+    let (old, new) = (load_version(args, old)?, load_version(args, new)?);
+    let diff = analysis::diff::compute(&old, &new);
+    print!("{}", analysis::diff::report(&diff));
+
+    let mut cytoscape_repr = cytoscape::from_ir_with(new.into_iter(), &args.output_options());
+    cytoscape::highlight_diff(&mut cytoscape_repr, &diff);
+    cytoscape_repr.check_ids()?;
+    for output in outputs {
+        if output.format == "html" {
+            write_file(
+                &output.path,
+                html::render(&cytoscape_repr, args.assets.as_deref().map(Path::new))?,
+            )?;
+        } else {
+            write_file(
+                &output.path,
+                serde_json::to_string_pretty(&cytoscape_repr).expect("Failed to pretty-print JSON"),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn semver(args: &cli::Args, old: &str, new: &str) -> anyhow::Result<()> {
+    let (old, new) = (load_version(args, old)?, load_version(args, new)?);
+    print!("{}", analysis::semver::report(&analysis::semver::compare(&old, &new)?));
+    Ok(())
+}
+
+// Reads an IR dump or, if there is no such file, traverses a git revision of
+// the project.
+fn load_version(args: &cli::Args, version: &str) -> anyhow::Result<Vec<ir::Package>> {
+    if Path::new(version).is_file() {
+        let dump = std::fs::read_to_string(version)?;
+        return serde_json::from_str(&dump)
+            .with_context(|| format!("Cannot parse the IR dump {version}"));
+    }
+
+    let worktree = git::Worktree::checkout(Path::new(&args.proj), version)?;
+    let proj = worktree.proj().to_str().context("The project path must be valid UTF-8")?;
+    traverse(&cli::Args { proj: proj.to_owned(), ..args.clone() })
+}
+
+pub(super) fn metrics(args: &cli::Args, json: bool) -> anyhow::Result<()> {
+    let packages = traverse(args)?;
+    let report = analysis::coupling::compute(&packages);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).expect("Failed to pretty-print JSON"));
+    } else {
+        print!("{}", analysis::coupling::table(&report));
+    }
+    Ok(())
+}
+
+pub(super) fn serve(args: &cli::Args, port: u16) -> anyhow::Result<()> {
+    let cache = Cache::new(args.cache_dir.as_ref().map(PathBuf::from));
+    let server = Server::new(cytoscape_dump(args, &cache)?);
+    server.listen(port)?;
+    log::info!("Serving the codebase at http://localhost:{port}/index.html.");
+
+    watch(args, &cache, || {
+        server.publish(cytoscape_dump(args, &cache)?);
+        Ok(())
+    })
+}
+
+// Sets up the traversal with the output filters and collapsing.
+fn ontology(args: &cli::Args) -> OntologyBuilder {
+    let builder = Ontology::builder().options(args.traversal()).filter(args.filter());
+    match args.collapse_depth {
+        Some(depth) => builder.collapse_depth(depth),
+        None => builder,
+    }
+}
+
+// Traverses the project without the output filters and collapsing, reusing
+// the files persisted in `--cache-dir`, if any.
+fn traverse(args: &cli::Args) -> anyhow::Result<Vec<ir::Package>> {
+    let cache = Cache::new(args.cache_dir.as_ref().map(PathBuf::from));
+    Ok(Ontology::builder().options(args.traversal()).build_with(&cache)?.into_packages())
+}
+
+// Traverses the project with all private items and modules, whose `use`s and
+// paths count as dependencies regardless of `--visibility`.
+fn traverse_private(args: &cli::Args) -> anyhow::Result<Vec<ir::Package>> {
+    let cache = Cache::new(args.cache_dir.as_ref().map(PathBuf::from));
+    let options = traverser::Options { visibility: ir::Visibility::Private, ..args.traversal() };
+    Ok(Ontology::builder().options(options).build_with(&cache)?.into_packages())
+}
+
+// Traverses the project into a Cytoscape JSON dump.
+fn cytoscape_dump(args: &cli::Args, cache: &Cache) -> anyhow::Result<String> {
+    let ir = ontology(args).build_with(cache)?.into_packages().into_iter();
+    let repr = cytoscape::from_ir_with(ir, &args.output_options());
+    repr.check_ids()?;
+    Ok(serde_json::to_string(&repr).expect("Failed to serialize JSON"))
+}
+
+// Calls `on_change` after every change in the project, forever. Only the
+// changed files are parsed again.
+fn watch(
+    args: &cli::Args,
+    cache: &Cache,
+    mut on_change: impl FnMut() -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut watcher = Watcher::new(traverser::members(&args.traversal())?);
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let changed = watcher.poll();
+        if changed.is_empty() {
+            continue;
+        }
+
+        log::info!("{} file(s) changed; regenerating the dump.", changed.len());
+        cache.invalidate(&changed);
+        // The set of workspace members might have changed as well.
+        if changed.iter().any(|path| path.ends_with("Cargo.toml")) {
+            match traverser::members(&args.traversal()) {
+                Ok(members) => watcher = Watcher::new(members),
+                Err(e) => log::error!("Failed to read workspace members: {e}."),
+            }
+        }
+        if let Err(e) = on_change() {
+            log::error!("Failed to regenerate the dump: {e}.");
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! The project configuration file, `ontologist.toml` next to the root
+//! `Cargo.toml` by default.
+//!
+//! Top-level keys set the global options under their command-line names, and
+//! the `[rules]` table holds the architecture rules:
+//!
+//! ```toml
+//! format = "html"
+//! output = ["codebase.html", "ir=ir.json"]
+//! enable-edges = true
+//! visibility = "public"
+//! features = ["serde"]
+//! exclude = ["**::tests"]
+//! collapse-depth = 2
+//!
+//! [[rules.forbidden-dependencies]]
+//! from = "app::lib::domain"
+//! to = "app::lib::infra"
+//! ```
+//!
+//! Options given on the command line override the file. There is no option
+//! for edge kinds: modules only have dependency edges, which `enable-edges`
+//! turns on.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use clap::{parser::ValueSource, ArgMatches};
+use serde::Deserialize;
+
+use crate::{analysis::rules::Rules, output::Registry, pattern::PathPattern};
+
+use crate::cli;
+
+/// The contents of a configuration file. Options are all optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub crate_roots: Option<String>,
+    pub rust_project: Option<String>,
+    pub output: Option<Outputs>,
+    pub format: Option<String>,
+    pub assets: Option<String>,
+    pub palette: Option<Vec<String>>,
+    pub enable_edges: Option<bool>,
+    pub visibility: Option<String>,
+    pub cfg: Option<Vec<String>>,
+    pub features: Option<Vec<String>>,
+    pub cache_dir: Option<String>,
+    pub history: Option<bool>,
+    pub history_since: Option<String>,
+    pub root: Option<String>,
+    pub max_depth: Option<usize>,
+    pub include: Option<Vec<PathPattern>>,
+    pub exclude: Option<Vec<PathPattern>>,
+    pub kind: Option<Vec<String>>,
+    pub focus: Option<String>,
+    pub hops: Option<usize>,
+    pub collapse_depth: Option<usize>,
+    #[serde(default)]
+    pub rules: Rules,
+}
+
+/// One output or a list of them, e.g., `["dump.json", "ir=ir.json"]`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Outputs {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl From<Outputs> for Vec<String> {
+    fn from(outputs: Outputs) -> Self {
+        match outputs {
+            Outputs::One(output) => vec![output],
+            Outputs::Many(outputs) => outputs,
+        }
+    }
+}
+
+/// Reads the file given by `--config` or, if there is none, `ontologist.toml`
+/// in the project root, if it exists.
+pub fn load(args: &cli::Args, registry: &Registry) -> anyhow::Result<Option<Config>> {
+    let path = match &args.config {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = [&args.proj, "ontologist.toml"].iter().collect::<PathBuf>();
+            if !path.is_file() {
+                return Ok(None);
+            }
+            path
+        }
+    };
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Cannot open {}", path.display()))?;
+    let config = toml::from_str::<Config>(&contents)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    if let Some(format) = &config.format {
+        if registry.get(format).is_none() {
+            bail!("Unknown format `{format}` in {}", path.display());
+        }
+    }
+    if let Some(visibility) = &config.visibility {
+        if !cli::VISIBILITIES.contains(&visibility.as_str()) {
+            bail!("Unknown visibility `{visibility}` in {}", path.display());
+        }
+    }
+    for kind in config.kind.iter().flatten() {
+        if !cli::KINDS.contains(&kind.as_str()) {
+            bail!("Unknown item kind `{kind}` in {}", path.display());
+        }
+    }
+    Ok(Some(config))
+}
+
+/// Sets the options of `args` that `matches` does not have from the command
+/// line to their values in `config`.
+pub fn merge(config: &Config, args: &mut cli::Args, matches: &ArgMatches) {
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    macro_rules! merge {
+        ($($option:ident),*) => {
+            $(if let (Some(value), false) = (&config.$option, from_cli(stringify!($option))) {
+                args.$option = value.clone().into();
+            })*
+        };
+    }
+    merge!(
+        crate_roots,
+        rust_project,
+        output,
+        format,
+        assets,
+        palette,
+        enable_edges,
+        visibility,
+        cfg,
+        features,
+        cache_dir,
+        history,
+        history_since,
+        root,
+        max_depth,
+        include,
+        exclude,
+        kind,
+        focus,
+        hops,
+        collapse_depth
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::Visibility;
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+
+    #[test]
+    fn merge_config() {
+        let config = toml::from_str::<Config>(
+            r##"
+            format = "html"
+            output = ["codebase.html", "ir=ir.json"]
+            enable-edges = true
+            visibility = "public"
+            features = ["serde"]
+            palette = ["#000", "rgb(1, 2, 3)"]
+            exclude = ["**::tests"]
+            collapse-depth = 2
+
+            [[rules.forbidden-dependencies]]
+            from = "app::lib::domain"
+            to = "app::lib::infra"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.rules.forbidden_dependencies.len(), 1);
+
+        let matches = cli::Args::command()
+            .try_get_matches_from(["rust-ontologist", "-p", ".", "-o", "dump.html", "metrics"])
+            .unwrap();
+        let mut args = cli::Args::from_arg_matches(&matches).unwrap();
+        merge(&config, &mut args, &matches);
+        assert_eq!(args.format, "html");
+        assert_eq!(args.output, vec!["dump.html"]);
+        assert!(args.enable_edges);
+        assert_eq!(args.exclude, vec!["**::tests".parse().unwrap()]);
+        assert_eq!(args.collapse_depth, Some(2));
+        assert_eq!(args.hops, 1);
+        assert_eq!(args.palette, vec!["#000", "rgb(1, 2, 3)"]);
+        let traversal = args.traversal();
+        assert_eq!(traversal.visibility, Visibility::Public);
+        assert_eq!(traversal.cfg, Some(vec![r#"feature="serde""#.to_owned()]));
+
+        assert!(toml::from_str::<Config>("enable-edge = true").is_err());
+
+        merge(&config, &mut args, &cli::Args::command().get_matches_from(["", "-p", "."]));
+        assert_eq!(
+            args.outputs(&Registry::default()),
+            vec![
+                cli::Output { format: "html".to_owned(), path: "codebase.html".to_owned() },
+                cli::Output { format: "ir".to_owned(), path: "ir.json".to_owned() },
+            ]
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! A local HTTP server for the Cytoscape viewer.
+//!
+//! It serves `index.html` and the current dump from memory. The page
+//! subscribes to `/events` ([server-sent events]) and reloads itself whenever
+//! a new dump is published.
+//!
+//! [server-sent events]: https://html.spec.whatwg.org/multipage/server-sent-events.html
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+
+use crate::output::html;
+
+// How often idle event streams are pinged to detect closed connections.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+        new EventSource("/events").onmessage = () => location.reload();
+    </script>
+</body>"#;
+
+pub struct Server {
+    state: Mutex<State>,
+    updated: Condvar,
+}
+
+struct State {
+    dump: String,
+    generation: u64,
+}
+
+impl Server {
+    pub fn new(dump: String) -> Arc<Self> {
+        Arc::new(Self { state: Mutex::new(State { dump, generation: 0 }), updated: Condvar::new() })
+    }
+
+    /// Replaces the served dump and reloads all connected pages.
+    pub fn publish(&self, dump: String) {
+        let mut state = self.state.lock().expect("Poisoned server state");
+        state.dump = dump;
+        state.generation += 1;
+        self.updated.notify_all();
+    }
+
+    /// Starts accepting connections on `localhost:{port}` in the background.
+    pub fn listen(self: &Arc<Self>, port: u16) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .with_context(|| format!("Cannot listen on port {port}"))?;
+
+        let server = Arc::clone(self);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = Arc::clone(&server);
+                std::thread::spawn(move || {
+                    if let Err(e) = server.handle(stream) {
+                        log::debug!("Failed to handle a request: {e}.");
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+
+    fn handle(&self, mut stream: TcpStream) -> anyhow::Result<()> {
+        let path = read_request_path(&stream)?;
+        log::trace!("GET {path}.");
+
+        match path.split('?').next().unwrap_or_default() {
+            "/" | "/index.html" => {
+                let page = html::TEMPLATE.replacen("</body>", LIVE_RELOAD_SCRIPT, 1);
+                respond(&mut stream, "200 OK", "text/html; charset=utf-8", &page)
+            }
+            "/codebase-dump.json" => {
+                let dump = self.state.lock().expect("Poisoned server state").dump.clone();
+                respond(&mut stream, "200 OK", "application/json", &dump)
+            }
+            "/events" => self.stream_events(stream),
+            _ => respond(&mut stream, "404 Not Found", "text/plain", "Not found"),
+        }
+    }
+
+    fn stream_events(&self, mut stream: TcpStream) -> anyhow::Result<()> {
+        stream.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: \
+              no-cache\r\nConnection: keep-alive\r\n\r\n",
+        )?;
+
+        let mut state = self.state.lock().expect("Poisoned server state");
+        let mut generation = state.generation;
+        loop {
+            if state.generation == generation {
+                state = self
+                    .updated
+                    .wait_timeout(state, KEEP_ALIVE_INTERVAL)
+                    .expect("Poisoned server state")
+                    .0;
+            }
+
+            let message: &[u8] =
+                if state.generation != generation { b"data: reload\n\n" } else { b": ping\n\n" };
+            generation = state.generation;
+
+            // Do not block publishers while writing to a slow client.
+            drop(state);
+            stream.write_all(message)?;
+            stream.flush()?;
+            state = self.state.lock().expect("Poisoned server state");
+        }
+    }
+}
+
+// Reads the request head and returns the path of a `GET` request.
+fn read_request_path(stream: &TcpStream) -> anyhow::Result<String> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["GET", path, _version] => Ok(path.to_owned()),
+        _ => Err(anyhow!("Unsupported request {:?}", request_line.trim())),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nCache-Control: \
+         no-store\r\nConnection: close\r\n\r\n{body}",
+        body.len(),
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    // Sends `GET {path}` to a fresh connection handled by `server`.
+    fn get(server: &Arc<Server>, path: &str) -> TcpStream {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let server = Arc::clone(server);
+        std::thread::spawn(move || server.handle(stream));
+        write!(client, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        client
+    }
+
+    #[test]
+    fn publish_and_reload() {
+        let server = Server::new("{}".to_owned());
+        let mut events = get(&server, "/events");
+        events.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+
+        server.publish(r#"{"elements":[]}"#.to_owned());
+        let mut response = String::new();
+        get(&server, "/codebase-dump.json").read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n{\"elements\":[]}"));
+
+        // The event stream might have subscribed after the first publication.
+        let mut received = String::new();
+        for _ in 0..50 {
+            let mut buf = [0; 1024];
+            if let Ok(n) = events.read(&mut buf) {
+                received.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+            }
+            if received.contains("data: reload\n\n") {
+                break;
+            }
+            server.publish("{}".to_owned());
+        }
+        assert!(received.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream"));
+        assert!(received.contains("data: reload\n\n"));
+    }
+}
@@ -0,0 +1,110 @@
+//! Polling for changes in project source files.
+//!
+//! A watcher remembers the modification times of all `.rs` and `Cargo.toml`
+//! files under a set of directories and reports which of them were modified,
+//! created, or removed since the last poll.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub struct Watcher {
+    dirs: Vec<PathBuf>,
+    snapshot: HashMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        let snapshot = take_snapshot(&dirs);
+        Self { dirs, snapshot }
+    }
+
+    /// Returns the files changed since the last poll.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let snapshot = take_snapshot(&self.dirs);
+
+        let mut changed = snapshot
+            .iter()
+            .filter(|(path, modified)| self.snapshot.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        changed.extend(self.snapshot.keys().filter(|path| !snapshot.contains_key(*path)).cloned());
+        changed.sort();
+
+        self.snapshot = snapshot;
+        changed
+    }
+}
+
+fn take_snapshot(dirs: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+    let mut snapshot = HashMap::new();
+    for dir in dirs {
+        walk_dir(dir, &mut snapshot);
+    }
+    snapshot
+}
+
+fn walk_dir(dir: &Path, snapshot: &mut HashMap<PathBuf, SystemTime>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            // Build artifacts and VCS metadata are never part of the ontology.
+            if file_name != "target" && !file_name.starts_with('.') {
+                walk_dir(&path, snapshot);
+            }
+        } else if is_watched_file(&path) {
+            if let Ok(modified) = metadata.modified() {
+                snapshot.insert(path, modified);
+            }
+        }
+    }
+}
+
+pub fn is_watched_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "rs")
+        || path.file_name().is_some_and(|name| name == "Cargo.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, time::Duration};
+
+    use super::*;
+
+    #[test]
+    fn poll() {
+        let dir = std::env::temp_dir().join(format!("ontologist-watcher-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        let (lib, main) = (dir.join("lib.rs"), dir.join("main.rs"));
+        std::fs::write(&lib, "").unwrap();
+        std::fs::write(&main, "").unwrap();
+
+        let mut watcher = Watcher::new(vec![dir.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        // Modification times might be too coarse to tell writes apart.
+        let later = SystemTime::now() + Duration::from_secs(10);
+        File::options().write(true).open(&lib).unwrap().set_modified(later).unwrap();
+        std::fs::remove_file(&main).unwrap();
+        let new = dir.join("new.rs");
+        std::fs::write(&new, "").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+        std::fs::write(dir.join("target").join("build.rs"), "").unwrap();
+        assert_eq!(watcher.poll(), vec![lib, main, new]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
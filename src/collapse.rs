@@ -0,0 +1,119 @@
+//! Collapsing deep modules into single vertices, to make big graphs readable.
+//!
+//! A collapsed module absorbs the code metrics, `use` statements, and
+//! dependencies of all its items and submodules, which are dropped. All
+//! dependencies on anything inside it are redirected to it, so that the
+//! outputs can count them, e.g., `net -> runtime (17 uses)`. Dependencies
+//! within a collapsed module are dropped.
+
+use std::collections::HashSet;
+
+use crate::ir::{id, ItemCollection, Mod, Package};
+
+/// Collapses all modules nested `depth` levels below crate roots, e.g., the
+/// top-level modules of crates for `1`.
+pub fn apply(depth: usize, packages: &mut [Package]) {
+    let mut collapsed = HashSet::new();
+    for package in packages.iter() {
+        package.walk_mods(&mut |id, _| {
+            if id.matches("::").count() == depth + 1 {
+                collapsed.insert(id.to_owned());
+            }
+        });
+    }
+
+    for package in packages {
+        for crate_ in &mut package.crates {
+            let id = format!("{}::{}", package.name, crate_.name);
+            collapse_mod(crate_, &id, 0, depth, &collapsed);
+        }
+    }
+}
+
+fn collapse_mod(
+    module: &mut Mod,
+    id: &str,
+    depth: usize,
+    max_depth: usize,
+    collapsed: &HashSet<String>,
+) {
+    if depth == max_depth {
+        let mut absorbed = std::mem::take(&mut module.items.mods);
+        while let Some(submodule) = absorbed.pop() {
+            absorb(module, submodule, &mut absorbed);
+        }
+        module.items =
+            ItemCollection { uses: std::mem::take(&mut module.items.uses), ..Default::default() };
+    }
+
+    let redirect = |deps: &mut Vec<String>| {
+        for dep in deps.iter_mut() {
+            let target = id::ancestors(dep).find(|path| collapsed.contains(*path));
+            if let Some(target) = target.map(ToOwned::to_owned) {
+                *dep = target;
+            }
+        }
+        deps.retain(|dep| dep != id);
+    };
+    redirect(&mut module.deps);
+    for use_ in &mut module.items.uses {
+        redirect(&mut use_.deps);
+    }
+
+    for submodule in &mut module.items.mods {
+        let submodule_id = format!("{id}::{}", submodule.name);
+        collapse_mod(submodule, &submodule_id, depth + 1, max_depth, collapsed);
+    }
+}
+
+// Moves the code metrics, `use` statements, and dependencies of `submodule`
+// into `module`, and its own submodules into `rest`.
+fn absorb(module: &mut Mod, mut submodule: Mod, rest: &mut Vec<Mod>) {
+    let (metrics, other) = (&mut module.metrics, &submodule.metrics);
+    metrics.lines += other.lines;
+    metrics.items += other.items;
+    metrics.fns += other.fns;
+    metrics.fn_lines += other.fn_lines;
+    metrics.complexity += other.complexity;
+
+    module.deps.append(&mut submodule.deps);
+    module.paths.append(&mut submodule.paths);
+    module.items.uses.append(&mut submodule.items.uses);
+    rest.append(&mut submodule.items.mods);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Struct, Visibility};
+
+    #[test]
+    fn collapse() {
+        let mut tcp = Mod::new("tcp");
+        tcp.metrics.lines = 10;
+        tcp.deps.extend(["app::lib::runtime::spawn".to_owned(), "app::lib::net::Addr".to_owned()]);
+        let mut net = Mod::new("net");
+        net.metrics.lines = 5;
+        net.items.structs.push(Struct {
+            name: "Addr".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        net.deps.extend(["app::lib::runtime::task::Id".to_owned(), "std::io".to_owned()]);
+        net.items.mods.push(tcp);
+        let runtime = Mod::new("runtime");
+        let mut lib = Mod::new("lib");
+        lib.deps.push("app::lib::net::tcp".to_owned());
+        lib.items.mods.extend([net, runtime]);
+        let mut packages = vec![Package { name: "app".to_owned(), crates: vec![lib] }];
+
+        apply(1, &mut packages);
+        let lib = &packages[0].crates[0];
+        assert_eq!(lib.deps, vec!["app::lib::net"]);
+        let net = &lib.items.mods[0];
+        assert!(net.items.mods.is_empty() && net.items.structs.is_empty());
+        assert_eq!(net.metrics.lines, 15);
+        assert_eq!(net.deps, vec!["app::lib::runtime", "std::io", "app::lib::runtime"]);
+    }
+}
@@ -0,0 +1,313 @@
+//! Narrowing the IR down to a part of the project before it is output.
+//!
+//! Filters select modules and items by their IDs. A module that is not
+//! selected itself but contains selected modules or items is kept as an empty
+//! shell without dependencies, so that the outputs can still nest its
+//! contents. Dependencies on modules and items that are filtered out are
+//! dropped.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::bail;
+
+use crate::{
+    ir::{self, Mod, Package},
+    pattern::PathPattern,
+};
+
+/// The filters of the output, all optional.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// The ID of the only module to output, with its descendants.
+    pub root: Option<String>,
+    /// The maximum nesting depth of modules below crate roots.
+    pub max_depth: Option<usize>,
+    /// Patterns of which at least one must match a module or item.
+    pub include: Vec<PathPattern>,
+    /// Patterns of which none may match a module or item.
+    pub exclude: Vec<PathPattern>,
+    /// The item kinds to output, e.g., `fn`.
+    pub kinds: Vec<String>,
+    /// The ID of a module or item and the number of dependency hops around it
+    /// to output.
+    pub focus: Option<(String, usize)>,
+}
+
+impl Filter {
+    fn is_empty(&self) -> bool {
+        self.root.is_none()
+            && self.max_depth.is_none()
+            && self.include.is_empty()
+            && self.exclude.is_empty()
+            && self.kinds.is_empty()
+            && self.focus.is_none()
+    }
+}
+
+/// Applies `filter` to `packages`, dropping the packages and crates that end up
+/// empty.
+pub fn apply(filter: &Filter, packages: Vec<Package>) -> anyhow::Result<Vec<Package>> {
+    if filter.is_empty() {
+        return Ok(packages);
+    }
+
+    let mut ids = HashSet::new();
+    for package in &packages {
+        package.walk_mods(&mut |id, module| {
+            ids.insert(id.to_owned());
+            for item in module.items.named_items() {
+                ids.insert(item.path(id));
+            }
+        });
+    }
+    if let Some(root) = &filter.root {
+        if !ids.contains(root) {
+            bail!("No module `{root}` found");
+        }
+    }
+    let neighbourhood = match &filter.focus {
+        Some((focus, _)) if !ids.contains(focus) => bail!("No module or item `{focus}` found"),
+        Some((focus, hops)) => Some(neighbourhood(&packages, &ids, focus, *hops)),
+        None => None,
+    };
+    let selector = Selector { filter, neighbourhood };
+
+    let mut filtered = vec![];
+    for mut package in packages {
+        package.crates = std::mem::take(&mut package.crates)
+            .into_iter()
+            .filter_map(|crate_| {
+                let id = format!("{}::{}", package.name, crate_.name);
+                selector.filter_mod(crate_, &id, 0)
+            })
+            .collect();
+        if !package.crates.is_empty() {
+            filtered.push(package);
+        }
+    }
+
+    let mut kept = HashSet::new();
+    for package in &filtered {
+        package.walk_mods(&mut |id, module| {
+            kept.insert(id.to_owned());
+            for item in module.items.named_items() {
+                kept.insert(item.path(id));
+            }
+        });
+    }
+    for package in &mut filtered {
+        for crate_ in &mut package.crates {
+            retain_deps(crate_, &|dep| !ids.contains(dep) || kept.contains(dep));
+        }
+    }
+    Ok(filtered)
+}
+
+struct Selector<'a> {
+    filter: &'a Filter,
+    // The IDs within the hops around the focus, if any.
+    neighbourhood: Option<HashSet<String>>,
+}
+
+impl Selector<'_> {
+    fn filter_mod(&self, mut module: Mod, id: &str, depth: usize) -> Option<Mod> {
+        if self.filter.max_depth.is_some_and(|max_depth| depth > max_depth)
+            || self.filter.exclude.iter().any(|pattern| pattern.matches(id))
+        {
+            return None;
+        }
+
+        let in_root = self
+            .filter
+            .root
+            .as_ref()
+            .is_none_or(|root| id == root || id.starts_with(&format!("{root}::")));
+        let is_selected = in_root && self.selects(id);
+
+        macro_rules! retain {
+            ($($items:ident: $kind:literal),*) => {
+                $(module.items.$items.retain(|item| {
+                    in_root
+                        && (self.filter.kinds.is_empty()
+                            || self.filter.kinds.iter().any(|kind| kind == $kind))
+                        && self.selects(&ir::id::item_path(id, &item.name))
+                });)*
+            };
+        }
+        retain!(
+            consts: "const",
+            enums: "enum",
+            fns: "fn",
+            statics: "static",
+            structs: "struct",
+            traits: "trait",
+            trait_aliases: "trait",
+            types: "type",
+            unions: "union"
+        );
+        module.items.mods = std::mem::take(&mut module.items.mods)
+            .into_iter()
+            .filter_map(|submodule| {
+                let submodule_id = format!("{id}::{}", submodule.name);
+                self.filter_mod(submodule, &submodule_id, depth + 1)
+            })
+            .collect();
+
+        if !is_selected {
+            if module.items.named_items().is_empty() && module.items.mods.is_empty() {
+                return None;
+            }
+            module.deps.clear();
+            module.items.impls.clear();
+        }
+        Some(module)
+    }
+
+    // Whether the include, exclude, and focus filters select a module or item.
+    fn selects(&self, id: &str) -> bool {
+        (self.filter.include.is_empty()
+            || self.filter.include.iter().any(|pattern| pattern.matches(id)))
+            && !self.filter.exclude.iter().any(|pattern| pattern.matches(id))
+            && self.neighbourhood.as_ref().is_none_or(|neighbourhood| neighbourhood.contains(id))
+    }
+}
+
+// Returns the IDs of the modules and items within `hops` dependencies of
+// `focus`, in either direction. The items of a focused module are part of the
+// focus.
+fn neighbourhood(
+    packages: &[Package],
+    ids: &HashSet<String>,
+    focus: &str,
+    hops: usize,
+) -> HashSet<String> {
+    let mut adjacency = HashMap::<&str, Vec<&str>>::new();
+    let mut neighbourhood = HashSet::from([focus.to_owned()]);
+    for package in packages {
+        package.walk_mods(&mut |id, module| {
+            if id == focus {
+                neighbourhood.extend(module.items.named_items().iter().map(|item| item.path(id)));
+            }
+            for dep in module.deps.iter().filter(|dep| ids.contains(*dep)) {
+                let (source, target) = (ids.get(id).expect("Unknown module"), dep.as_str());
+                adjacency.entry(source).or_default().push(target);
+                adjacency.entry(target).or_default().push(source);
+            }
+        });
+    }
+
+    let mut queue =
+        neighbourhood.iter().map(|id| (id.clone(), 0)).collect::<VecDeque<(String, usize)>>();
+    while let Some((id, distance)) = queue.pop_front() {
+        if distance == hops {
+            continue;
+        }
+        for &next in adjacency.get(id.as_str()).into_iter().flatten() {
+            if neighbourhood.insert(next.to_owned()) {
+                queue.push_back((next.to_owned(), distance + 1));
+            }
+        }
+    }
+    neighbourhood
+}
+
+fn retain_deps(module: &mut Mod, f: &impl Fn(&str) -> bool) {
+    module.deps.retain(|dep| f(dep));
+    for submodule in &mut module.items.mods {
+        retain_deps(submodule, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Fn, Struct, Visibility};
+
+    fn packages() -> Vec<Package> {
+        let item_fn = |name: &str| Fn {
+            name: name.to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            lines: 1,
+            complexity: 1,
+            ..Default::default()
+        };
+        let mut pool = Mod::new("pool");
+        pool.items.structs.push(Struct {
+            name: "Pool".to_owned(),
+            vis: Visibility::Public,
+            repr: String::new(),
+            ..Default::default()
+        });
+        let mut db = Mod::new("db");
+        db.items.fns.push(item_fn("connect"));
+        db.deps.push("app::lib::db::pool::Pool".to_owned());
+        db.items.mods.push(pool);
+        let mut api = Mod::new("api");
+        api.items.fns.push(item_fn("serve"));
+        api.deps.push("app::lib::db::connect".to_owned());
+        api.deps.push("std::net".to_owned());
+        let mut lib = Mod::new("lib");
+        lib.items.fns.push(item_fn("run"));
+        lib.deps.push("app::lib::api".to_owned());
+        lib.items.mods.extend([db, api]);
+        vec![Package { name: "app".to_owned(), crates: vec![lib] }]
+    }
+
+    // Lists the remaining module and item IDs, with deps after `->`.
+    fn ids(filter: Filter) -> Vec<String> {
+        let mut ids = vec![];
+        for package in apply(&filter, packages()).unwrap() {
+            package.walk_mods(&mut |id, module| {
+                ids.push(format!("{id} -> {}", module.deps.join(", ")));
+                for item in module.items.named_items() {
+                    ids.push(item.path(id));
+                }
+            });
+        }
+        ids
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            ids(Filter { root: Some("app::lib::db".to_owned()), ..Default::default() }),
+            vec![
+                "app::lib -> ",
+                "app::lib::db -> app::lib::db::pool::Pool",
+                "app::lib::db::connect",
+                "app::lib::db::pool -> ",
+                "app::lib::db::pool::Pool",
+            ]
+        );
+        assert_eq!(
+            ids(Filter {
+                max_depth: Some(1),
+                exclude: vec!["**::api".parse().unwrap()],
+                kinds: vec!["fn".to_owned()],
+                ..Default::default()
+            }),
+            vec!["app::lib -> ", "app::lib::run", "app::lib::db -> ", "app::lib::db::connect"]
+        );
+        assert_eq!(
+            ids(Filter { include: vec!["app::*::api".parse().unwrap()], ..Default::default() }),
+            vec!["app::lib -> ", "app::lib::api -> std::net", "app::lib::api::serve"]
+        );
+        assert_eq!(
+            ids(Filter { focus: Some(("app::lib::db".to_owned(), 1)), ..Default::default() }),
+            vec![
+                "app::lib -> ",
+                "app::lib::db -> app::lib::db::pool::Pool",
+                "app::lib::db::connect",
+                "app::lib::db::pool -> ",
+                "app::lib::db::pool::Pool",
+                "app::lib::api -> app::lib::db::connect, std::net",
+            ]
+        );
+        assert!(apply(
+            &Filter { root: Some("app::main".to_owned()), ..Default::default() },
+            vec![]
+        )
+        .is_err());
+    }
+}
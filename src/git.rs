@@ -0,0 +1,77 @@
+//! Access to the local git repository containing the project, through the
+//! `git` command.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{bail, Context};
+
+/// A temporary checkout of a revision, removed on drop.
+pub struct Worktree {
+    root: PathBuf,
+    path: PathBuf,
+    proj: PathBuf,
+}
+
+impl Worktree {
+    /// Checks out `rev` of the repository containing `proj` into a temporary
+    /// directory.
+    pub fn checkout(proj: &Path, rev: &str) -> anyhow::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let root = toplevel(proj)?;
+        // The project might be a subdirectory of the repository.
+        let prefix = run(proj, &["rev-parse", "--show-prefix"])?;
+        let path = std::env::temp_dir().join(format!(
+            "rust-ontologist-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let path_str = path.to_str().context("The temporary directory must be valid UTF-8")?;
+
+        run(&root, &["worktree", "add", "--detach", "--quiet", path_str, rev])
+            .with_context(|| format!("Cannot check out {rev}"))?;
+        let proj = path.join(prefix.trim());
+        Ok(Self { root, path, proj })
+    }
+
+    /// The project directory within the checkout.
+    pub fn proj(&self) -> &Path {
+        &self.proj
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let Some(path) = self.path.to_str() else {
+            return;
+        };
+        if let Err(e) = run(&self.root, &["worktree", "remove", "--force", path]) {
+            log::error!("Failed to remove the worktree {path}: {e}.");
+        }
+    }
+}
+
+/// Returns the canonical root directory of the repository containing `dir`.
+pub fn toplevel(dir: &Path) -> anyhow::Result<PathBuf> {
+    let root = PathBuf::from(run(dir, &["rev-parse", "--show-toplevel"])?.trim());
+    Ok(std::fs::canonicalize(&root).unwrap_or(root))
+}
+
+/// Runs `git` in `dir`, returning its standard output.
+pub fn run(dir: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output =
+        Command::new("git").arg("-C").arg(dir).args(args).output().context("Cannot run `git`")?;
+    if !output.status.success() {
+        bail!(
+            "`git {}` failed in {}: {}",
+            args.first().unwrap_or(&""),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context("`git` printed invalid UTF-8")
+}
@@ -0,0 +1,180 @@
+//! Git history of modules, read from the local repository containing the
+//! project.
+//!
+//! Commits are read with `git log`, so only the local `.git` directory is
+//! needed. The history of a module covers its own file and the files of all
+//! its submodules, so the history of a crate root covers the whole crate.
+//! Modules whose files were not touched within the window have no history.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    git,
+    ir::{History, Mod, Package},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Separators of `git log` records and their header fields. With `-z`, the
+// header and the changed files are terminated by NUL.
+const RECORD_SEPARATOR: char = '\x1e';
+const FIELD_SEPARATOR: char = '\x1f';
+
+#[derive(Debug, Eq, PartialEq)]
+struct Commit {
+    // The committer date as a Unix timestamp.
+    timestamp: u64,
+    // The committer date, e.g., `2024-07-04`.
+    date: String,
+    author: String,
+    // Changed files, relative to the repository root.
+    files: Vec<PathBuf>,
+}
+
+/// Attaches the history since `since`, in any format accepted by
+/// `git log --since`, to all modules of `packages`.
+pub fn overlay(proj: &Path, since: &str, packages: &mut [Package]) -> anyhow::Result<()> {
+    let root = git::toplevel(proj).context("Cannot read the git history")?;
+
+    let log = git::run(
+        proj,
+        &[
+            "log",
+            &format!("--since={since}"),
+            "--no-merges",
+            "--no-renames",
+            "--name-only",
+            // Without `-z`, non-ASCII paths are quoted and escaped.
+            "-z",
+            &format!("--format={RECORD_SEPARATOR}%ct{FIELD_SEPARATOR}%cs{FIELD_SEPARATOR}%aE"),
+            "--",
+            ".",
+        ],
+    )?;
+    let commits = parse_log(&log)?;
+    log::debug!("Read {} commit(s) since {since}.", commits.len());
+
+    let mut commits_by_file = HashMap::<PathBuf, Vec<usize>>::new();
+    for (i, commit) in commits.iter().enumerate() {
+        for file in &commit.files {
+            commits_by_file.entry(root.join(file)).or_default().push(i);
+        }
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    for package in packages {
+        for crate_ in &mut package.crates {
+            attach(crate_, &commits, &commits_by_file, now);
+        }
+    }
+    Ok(())
+}
+
+// Attaches the history to `module` and its submodules, returning the indices
+// of the commits that touched them.
+fn attach(
+    module: &mut Mod,
+    commits: &[Commit],
+    commits_by_file: &HashMap<PathBuf, Vec<usize>>,
+    now: u64,
+) -> HashSet<usize> {
+    let mut touched = HashSet::new();
+    for file in module.file.iter().chain(&module.alternative_files) {
+        touched.extend(commits_by_file.get(file).into_iter().flatten());
+    }
+    for submodule in &mut module.items.mods {
+        touched.extend(attach(submodule, commits, commits_by_file, now));
+    }
+
+    let authors = touched.iter().map(|&i| &commits[i].author).collect::<HashSet<_>>();
+    let last = touched.iter().map(|&i| &commits[i]).max_by_key(|commit| commit.timestamp);
+    module.history = last.map(|last| History {
+        commits: touched.len(),
+        authors: authors.len(),
+        last_modified: last.date.clone(),
+        age_days: now.saturating_sub(last.timestamp) / SECONDS_PER_DAY,
+    });
+    touched
+}
+
+fn parse_log(log: &str) -> anyhow::Result<Vec<Commit>> {
+    log.split(RECORD_SEPARATOR)
+        .filter(|record| !record.trim().is_empty())
+        .map(|record| {
+            let (header, files) = record.split_once('\0').unwrap_or((record, ""));
+            let files = files.strip_prefix('\n').unwrap_or(files);
+            let [timestamp, date, author] = header.split(FIELD_SEPARATOR).collect::<Vec<_>>()[..]
+            else {
+                bail!("Unexpected `git log` header: {header:?}");
+            };
+            Ok(Commit {
+                timestamp: timestamp
+                    .parse()
+                    .with_context(|| format!("Invalid commit timestamp {timestamp:?}"))?,
+                date: date.to_owned(),
+                author: author.to_lowercase(),
+                files: files
+                    .split('\0')
+                    .filter(|file| !file.is_empty())
+                    .map(PathBuf::from)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attach_history() {
+        let log = "\x1e1720051200\x1f2024-07-04\x1fAlice@example.com\0\nsrc/lib.rs\0src/db.rs\0\
+                   \x1e1719964800\x1f2024-07-03\x1fbob@example.com\0\nsrc/db.rs\0src/d\u{e9}j\u{e0}.rs\0\
+                   \x1e1719878400\x1f2024-07-02\x1falice@example.com\0\nREADME.md\0";
+        let commits = parse_log(log).unwrap();
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[0].files, vec![PathBuf::from("src/lib.rs"), PathBuf::from("src/db.rs")]);
+        assert_eq!(commits[1].files[1], PathBuf::from("src/déjà.rs"));
+
+        let mut commits_by_file = HashMap::<PathBuf, Vec<usize>>::new();
+        for (i, commit) in commits.iter().enumerate() {
+            for file in &commit.files {
+                commits_by_file.entry(Path::new("/repo").join(file)).or_default().push(i);
+            }
+        }
+
+        let mut db = Mod::new("db");
+        db.file = Some(PathBuf::from("/repo/src/db.rs"));
+        let mut lib = Mod::new("lib");
+        lib.file = Some(PathBuf::from("/repo/src/lib.rs"));
+        lib.items.mods.extend([db, Mod::new("inline")]);
+
+        attach(&mut lib, &commits, &commits_by_file, 1720051200 + 3 * SECONDS_PER_DAY);
+        assert_eq!(
+            lib.history,
+            Some(History {
+                commits: 2,
+                authors: 2,
+                last_modified: "2024-07-04".to_owned(),
+                age_days: 3
+            })
+        );
+        assert_eq!(
+            lib.items.mods[0].history,
+            Some(History {
+                commits: 2,
+                authors: 2,
+                last_modified: "2024-07-04".to_owned(),
+                age_days: 3
+            })
+        );
+        assert_eq!(lib.items.mods[1].history, None);
+    }
+}
@@ -1,29 +1,202 @@
 //! The IR (Intermediate Representation) of a project structure.
+//!
+//! Fields added to items after the first IR dumps default to empty, so that
+//! older dumps still load, e.g., as the old version in `diff` and `semver`.
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+pub mod id;
+
+use std::{path::PathBuf, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Package {
     pub name: String,
     pub crates: Vec<Mod>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Mod {
     pub name: String,
+    pub vis: Visibility,
     pub items: ItemCollection,
     pub deps: Vec<String>,
+    /// Paths with several segments in the module's own code, resolved like
+    /// the dependencies of `use`s, e.g., `ir::Mod::new` into
+    /// `pkg::lib::ir::Mod::new`. Collected with `--enable-edges` only.
+    pub paths: Vec<String>,
+    pub metrics: CodeMetrics,
+    /// The file the module is defined in, unless it is inline.
+    pub file: Option<PathBuf>,
+    /// The files of alternative definitions under other `#[cfg]`s, merged
+    /// into this one.
+    #[serde(default)]
+    pub alternative_files: Vec<PathBuf>,
+    /// Git history, if requested with `--history`.
+    pub history: Option<History>,
+}
+
+impl Package {
+    /// Calls `f` with every module of the package, including crate roots, and
+    /// its ID, e.g., `package::lib::foo`.
+    pub fn walk_mods<'a>(&'a self, f: &mut impl FnMut(&str, &'a Mod)) {
+        for crate_ in &self.crates {
+            crate_.walk_mods(&format!("{}::{}", self.name, crate_.name), f);
+        }
+    }
 }
 
 impl Mod {
     pub fn new(name: impl Into<String>) -> Self {
-        Self { name: name.into(), items: Default::default(), deps: vec![] }
+        Self {
+            name: name.into(),
+            vis: Visibility::Private,
+            items: Default::default(),
+            deps: vec![],
+            paths: vec![],
+            metrics: Default::default(),
+            file: None,
+            alternative_files: vec![],
+            history: None,
+        }
+    }
+
+    /// Calls `f` with this module, whose ID is `id`, and all its descendants.
+    pub fn walk_mods<'a>(&'a self, id: &str, f: &mut impl FnMut(&str, &'a Mod)) {
+        f(id, self);
+        for module in &self.items.mods {
+            module.walk_mods(&format!("{id}::{}", module.name), f);
+        }
+    }
+}
+
+impl ItemCollection {
+    /// Returns all items except modules and uses.
+    pub fn named_items(&self) -> Vec<NamedItem<'_>> {
+        macro_rules! named {
+            ($items:expr, $kind:literal) => {
+                named!($items, $kind, $kind)
+            };
+            ($items:expr, $kind:literal, $id_kind:literal) => {
+                $items.iter().map(|item| NamedItem {
+                    kind: $kind,
+                    id_kind: $id_kind,
+                    name: &item.name,
+                    vis: item.vis,
+                    repr: &item.repr,
+                })
+            };
+        }
+
+        let mut items = vec![];
+        items.extend(named!(self.consts, "const"));
+        items.extend(named!(self.enums, "enum"));
+        items.extend(named!(self.fns, "fn"));
+        items.extend(named!(self.statics, "static"));
+        items.extend(named!(self.structs, "struct"));
+        items.extend(named!(self.traits, "trait"));
+        items.extend(named!(self.trait_aliases, "trait", "trait_alias"));
+        items.extend(named!(self.types, "type"));
+        items.extend(named!(self.unions, "union"));
+        items
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Default)]
+impl NamedItem<'_> {
+    /// Returns the ID of the item in the module with ID `module`.
+    pub fn id(&self, module: &str) -> String {
+        id::item(module, self.id_kind, self.name)
+    }
+
+    /// Returns the path of the item in the module with ID `module`, which
+    /// items of other namespaces may share.
+    pub fn path(&self, module: &str) -> String {
+        id::item_path(module, self.name)
+    }
+}
+
+/// A view of an item of any kind except modules and uses.
+#[derive(Debug, Clone, Copy)]
+pub struct NamedItem<'a> {
+    /// The item kind, the same as in the outputs, e.g., `struct`.
+    pub kind: &'static str,
+    /// The item kind in IDs, which tells trait aliases from traits.
+    pub id_kind: &'static str,
+    pub name: &'a str,
+    pub vis: Visibility,
+    pub repr: &'a str,
+}
+
+/// Item visibility, ordered from the most to the least visible.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default, Serialize, Deserialize)]
+pub enum Visibility {
+    /// `pub`.
+    Public,
+    /// `pub(crate)`, `pub(super)`, or `pub(in path)`.
+    Restricted,
+    /// No visibility modifier.
+    #[default]
+    Private,
+}
+
+impl Visibility {
+    /// Whether an item of this visibility is visible at `level` or more, e.g.,
+    /// `pub` items are visible at the `Restricted` level.
+    pub fn is_at_least(self, level: Visibility) -> bool {
+        self <= level
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = anyhow::Error;
+
+    /// Parses `public`, `restricted`, or `private`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "public" => Ok(Self::Public),
+            "restricted" => Ok(Self::Restricted),
+            "private" => Ok(Self::Private),
+            _ => Err(anyhow::anyhow!("Unknown visibility `{s}`")),
+        }
+    }
+}
+
+/// Size and complexity of a module's own code, excluding the code of its
+/// non-inline submodules.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct CodeMetrics {
+    /// Lines of code, including comments and blank lines.
+    pub lines: usize,
+    /// The number of items, including private ones.
+    pub items: usize,
+    /// The number of functions and methods, including private ones.
+    pub fns: usize,
+    /// The total lines of code of the functions and methods.
+    pub fn_lines: usize,
+    /// The total cyclomatic complexity of the functions and methods.
+    pub complexity: usize,
+}
+
+/// Git history of the files of a module and all its submodules within the
+/// window given by `--history-since`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    /// The number of commits that touched the files.
+    pub commits: usize,
+    /// The number of distinct commit author emails.
+    pub authors: usize,
+    /// The date of the last commit, e.g., `2024-07-04`.
+    pub last_modified: String,
+    /// Days since the last commit.
+    pub age_days: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct ItemCollection {
     pub consts: Vec<Const>,
     pub enums: Vec<Enum>,
     pub fns: Vec<Fn>,
+    pub impls: Vec<Impl>,
     pub mods: Vec<Mod>,
     pub statics: Vec<Static>,
     pub structs: Vec<Struct>,
@@ -34,61 +207,301 @@ pub struct ItemCollection {
     pub uses: Vec<Use>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Const {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub ty: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Enum {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    #[serde(default)]
+    pub variants: Vec<Variant>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Fn {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub sig: Signature,
+    /// Lines of code, from the signature to the closing brace.
+    pub lines: usize,
+    pub complexity: usize,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Static {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub ty: String,
+    #[serde(default)]
+    pub mutable: bool,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Struct {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    #[serde(default)]
+    pub fields: Vec<Field>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Trait {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    /// The supertrait bounds as written, e.g., `Clone` or `'static`.
+    #[serde(default)]
+    pub supertraits: Vec<String>,
+    #[serde(default)]
+    pub items: AssocItems,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct TraitAlias {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    #[serde(default)]
+    pub bounds: Vec<String>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Type {
     pub name: String,
+    pub vis: Visibility,
     pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    /// The aliased type as written.
+    #[serde(default)]
+    pub ty: String,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub struct Union {
     pub name: String,
+    pub vis: Visibility,
+    pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    #[serde(default)]
+    pub fields: Vec<Field>,
+}
+
+/// An `impl` block, or a trait implementation generated by `#[derive]`.
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct Impl {
+    /// The implemented trait as written, e.g., `From<u32>`, if any.
+    pub trait_: Option<String>,
+    /// The implementing type as written, e.g., `Foo<T>`.
+    pub self_ty: String,
+    /// Whether the implementation comes from `#[derive]`.
+    pub derived: bool,
+    /// The header, e.g., `impl<T> From<T> for Foo<T> {}`.
     pub repr: String,
+    #[serde(default)]
+    pub generics: Generics,
+    /// The items of the block. Items of inherent implementations less visible
+    /// than the traversal's visibility level are skipped.
+    #[serde(default)]
+    pub items: AssocItems,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// Generic parameters and `where` clauses. Types and bounds here and in the
+/// other items are written without spaces, as in [`Impl::self_ty`].
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct Generics {
+    pub params: Vec<GenericParam>,
+    pub where_clause: Vec<WherePredicate>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GenericParam {
+    /// The name, e.g., `T`, or `'a` for lifetimes.
+    pub name: String,
+    pub kind: GenericParamKind,
+    /// The bounds, e.g., `Clone` or `'a`.
+    pub bounds: Vec<String>,
+    /// The type of a const parameter.
+    pub ty: Option<String>,
+    /// The default value, e.g., `String` in `T = String`.
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GenericParamKind {
+    Lifetime,
+    Type,
+    Const,
+}
+
+/// A predicate of a `where` clause, e.g., `T: Send + 'static`.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WherePredicate {
+    /// The bounded type or lifetime, e.g., `T`.
+    pub bounded: String,
+    pub bounds: Vec<String>,
+}
+
+/// A field of a struct, union, or enum variant. Fields of enum variants are
+/// public.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Field {
+    /// The name, unless it is a tuple field.
+    pub name: Option<String>,
+    pub vis: Visibility,
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Field>,
+    /// The explicit discriminant, e.g., `1 << 2`.
+    pub discriminant: Option<String>,
+}
+
+/// The signature of a function or method.
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct Signature {
+    pub generics: Generics,
+    /// The parameters, including the receiver, e.g., `&self`.
+    pub params: Vec<Param>,
+    /// The return type, unless it is `()`.
+    pub output: Option<String>,
+    pub is_async: bool,
+    pub is_const: bool,
+    pub is_unsafe: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Param {
+    /// The pattern, e.g., `x`, `(a, b)`, or `self` for receivers.
+    pub pat: String,
+    /// The type, e.g., `&mut Self` for `&mut self`.
+    pub ty: String,
+}
+
+/// The items of a trait or an `impl` block. Trait items and the items of trait
+/// implementations are public.
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct AssocItems {
+    pub consts: Vec<AssocConst>,
+    pub fns: Vec<AssocFn>,
+    pub types: Vec<AssocType>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AssocConst {
+    pub name: String,
+    pub vis: Visibility,
+    pub ty: String,
+    /// Whether the trait gives the value or the implementation overrides it.
+    pub has_value: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AssocFn {
+    pub name: String,
+    pub vis: Visibility,
+    pub sig: Signature,
+    /// Whether the trait gives a default body or the implementation overrides
+    /// it.
+    pub has_body: bool,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AssocType {
+    pub name: String,
+    pub vis: Visibility,
+    pub generics: Generics,
+    /// The bounds required by the trait, e.g., `Iterator<Item = u8>`.
+    pub bounds: Vec<String>,
+    /// The type given by the implementation or the trait's default.
+    pub ty: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Use {
+    pub vis: Visibility,
     pub repr: String,
+    /// The paths this `use` depends on, if edges are enabled, resolved against
+    /// the module, e.g., `super::Foo` in `pkg::lib::a` into `pkg::lib::Foo`.
+    pub deps: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_dumps() {
+        // An IR dump from before items had structured data.
+        let dump = r##"[{
+            "name": "app",
+            "crates": [{
+                "name": "lib",
+                "vis": "Private",
+                "items": {
+                    "consts": [{"name": "MAX", "vis": "Public", "repr": "pub const MAX: u8 = 1;"}],
+                    "enums": [],
+                    "fns": [{
+                        "name": "run",
+                        "vis": "Public",
+                        "repr": "pub fn run() {}",
+                        "lines": 1,
+                        "complexity": 1
+                    }],
+                    "impls": [{
+                        "trait_": "Clone",
+                        "self_ty": "Config",
+                        "derived": true,
+                        "repr": "#[derive(Clone)]"
+                    }],
+                    "mods": [],
+                    "statics": [],
+                    "structs": [{"name": "Config", "vis": "Public", "repr": "pub struct Config;"}],
+                    "traits": [{"name": "Plugin", "vis": "Public", "repr": "pub trait Plugin {}"}],
+                    "trait_aliases": [],
+                    "types": [],
+                    "unions": [],
+                    "uses": []
+                },
+                "deps": [],
+                "paths": [],
+                "metrics": {"lines": 4, "items": 4, "fns": 1, "fn_lines": 1, "complexity": 1},
+                "file": "src/lib.rs",
+                "history": null
+            }]
+        }]"##;
+
+        let packages = serde_json::from_str::<Vec<Package>>(dump).unwrap();
+        let items = &packages[0].crates[0].items;
+        assert_eq!(items.consts[0].ty, "");
+        assert_eq!(items.fns[0].sig, Signature::default());
+        assert_eq!(items.structs[0].fields, vec![]);
+        assert_eq!(items.traits[0].items, AssocItems::default());
+        assert_eq!(items.impls[0].generics, Generics::default());
+    }
 }
@@ -0,0 +1,88 @@
+//! Vertex and edge IDs of the graph outputs.
+//!
+//! | Vertex  | ID                        | Example              |
+//! |---------|---------------------------|----------------------|
+//! | package | `{package}`               | `syn`                |
+//! | crate   | `{package}::{crate}`      | `syn::lib`           |
+//! | module  | `{parent}::{module}`      | `syn::lib::parse`    |
+//! | item    | `{module}::{name}#{kind}` | `syn::lib::parse#fn` |
+//!
+//! The IDs of packages, crates, and modules are their paths, as in the
+//! dependencies of modules. Item IDs also have the item kind, e.g., `struct` or
+//! `trait_alias`, since names are unique only within a namespace: a module and
+//! a function, or a struct with named fields and a function, may share a name.
+//! The kind determines the namespace, so an ID names at most one item. The only
+//! exception in Rust code is alternative definitions under different
+//! `#[cfg]`s. Unless it evaluates `#[cfg]` (see
+//! [`traverser::Options::cfg`](crate::traverser::Options::cfg)), the traverser
+//! merges alternative modules and keeps only the first of alternative items.
+//! Any other repeated ID, e.g., of two crates with the same name in a
+//! hand-written IR, is an error in the outputs.
+//!
+//! Edge IDs are `{kind}:{source}->{target}`, e.g.,
+//! `dep:syn::lib::parse->syn::lib::Error#struct`, where `kind` is `dep` for
+//! dependencies.
+
+use std::collections::HashSet;
+
+use anyhow::bail;
+
+/// The name of library crates. Paths into other packages are assumed to lead
+/// into their library crates, e.g., `serde::Serialize` into
+/// `serde::lib::Serialize`.
+pub const LIB: &str = "lib";
+
+pub fn item(module: &str, kind: &str, name: &str) -> String {
+    format!("{}#{kind}", item_path(module, name))
+}
+
+/// Returns the path of an item, i.e., its ID without the kind, as in the
+/// dependencies of modules.
+pub fn item_path(module: &str, name: &str) -> String {
+    format!("{module}::{name}")
+}
+
+pub fn edge(kind: &str, source: &str, target: &str) -> String {
+    format!("{kind}:{source}->{target}")
+}
+
+/// Returns the path of a vertex, i.e., its ID without the item kind.
+pub fn path(id: &str) -> &str {
+    id.split_once('#').map_or(id, |(path, _)| path)
+}
+
+/// Returns `path` and all its parent paths, e.g., `pkg::lib::db`, `pkg`, and
+/// `pkg::lib` for `pkg::lib::db`.
+pub fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    std::iter::once(path).chain(path.match_indices("::").map(|(i, _)| &path[..i]))
+}
+
+/// Fails on the first ID that occurs more than once.
+pub fn check_unique<'a>(ids: impl IntoIterator<Item = &'a str>) -> anyhow::Result<()> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            bail!("Duplicate vertex or edge ID `{id}`");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids() {
+        let module = "syn::lib::parse";
+        let (func, struct_) =
+            (item("syn::lib", "fn", "parse"), item("syn::lib", "struct", "parse"));
+        assert_eq!(func, "syn::lib::parse#fn");
+        assert_eq!(path(&func), module);
+        assert_eq!(item_path("syn::lib", "parse"), module);
+        assert_eq!(path(module), module);
+        assert_eq!(ancestors(module).collect::<Vec<_>>(), [module, "syn", "syn::lib"]);
+        assert!(check_unique([module, &func, &struct_]).is_ok());
+        assert!(check_unique([module, &func, &edge("dep", module, &func), &func]).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+//! Extracting the structure of Rust codebases: their packages, crates,
+//! modules, items, and dependencies between them.
+//!
+//! A project is traversed into its [IR](ir), which the [analyses](analysis)
+//! work on and the [outputs](output) are rendered from:
+//!
+//! ```no_run
+//! use rust_ontologist::{output::cytoscape, Ontology};
+//!
+//! let ontology = Ontology::builder().project("path/to/project").enable_edges(true).build()?;
+//! for package in ontology.packages() {
+//!     println!("{}: {} crate(s)", package.name, package.crates.len());
+//! }
+//! let repr = cytoscape::from_ir(ontology.into_packages().into_iter());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! The `rust-ontologist` binary is [`cli::run`] with the built-in output
+//! formats.
+
+pub mod analysis;
+pub mod cli;
+pub mod collapse;
+mod crutches;
+pub mod filter;
+pub mod git;
+mod history;
+pub mod ir;
+pub mod manifest;
+mod ontology;
+pub mod output;
+pub mod pattern;
+pub mod roots;
+pub mod rust_project;
+mod syn_util;
+pub mod traverser;
+
+pub use self::ontology::{Ontology, OntologyBuilder};
@@ -0,0 +1,177 @@
+//! The entry point of the library: traversing a project into its IR, with the
+//! output filters and collapsing applied.
+
+use std::path::PathBuf;
+
+use crate::{
+    collapse,
+    filter::{self, Filter},
+    ir::{Package, Visibility},
+    traverser::{self, Cache, Options},
+};
+
+/// The IR of a traversed project.
+#[derive(Debug, Clone)]
+pub struct Ontology {
+    packages: Vec<Package>,
+}
+
+impl Ontology {
+    /// Returns a builder traversing the Cargo workspace in the current
+    /// directory for its `pub` and `pub(crate)` items in all `#[cfg]`
+    /// alternatives, without dependencies, history, or filters.
+    pub fn builder() -> OntologyBuilder {
+        OntologyBuilder {
+            options: Options { proj: ".".to_owned(), ..Default::default() },
+            filter: Filter::default(),
+            collapse_depth: None,
+            cache_dir: None,
+        }
+    }
+
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    pub fn into_packages(self) -> Vec<Package> {
+        self.packages
+    }
+}
+
+/// Options of an [`Ontology`], set with the methods named after the
+/// command-line options.
+#[derive(Debug, Clone)]
+pub struct OntologyBuilder {
+    options: Options,
+    filter: Filter,
+    collapse_depth: Option<usize>,
+    cache_dir: Option<PathBuf>,
+}
+
+impl OntologyBuilder {
+    /// Sets the project root directory, with the root `Cargo.toml`.
+    pub fn project(mut self, proj: impl Into<String>) -> Self {
+        self.options.proj = proj.into();
+        self
+    }
+
+    /// Traverses the crate roots listed in a file instead of the Cargo
+    /// workspace. See [`crate::roots`] for the format.
+    pub fn crate_roots(mut self, list: impl Into<String>) -> Self {
+        self.options.crate_roots = Some(list.into());
+        self
+    }
+
+    /// Traverses the crates of a rust-analyzer `rust-project.json` file instead
+    /// of the Cargo workspace.
+    pub fn rust_project(mut self, path: impl Into<String>) -> Self {
+        self.options.rust_project = Some(path.into());
+        self
+    }
+
+    /// Extracts dependencies between modules and items.
+    pub fn enable_edges(mut self, enable_edges: bool) -> Self {
+        self.options.enable_edges = enable_edges;
+        self
+    }
+
+    /// Adds the git history of modules since `since`, e.g., `1 year ago`.
+    pub fn history(mut self, since: impl Into<String>) -> Self {
+        self.options.history = Some(since.into());
+        self
+    }
+
+    /// Extracts the items at least as visible as `level`.
+    pub fn visibility(mut self, level: Visibility) -> Self {
+        self.options.visibility = level;
+        self
+    }
+
+    /// Extracts only the items enabled by `#[cfg]` attributes with exactly the
+    /// given options set, e.g., `unix` or `feature = "serde"`.
+    pub fn cfg(mut self, options: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.options.cfg = Some(options.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Replaces all the traversal options at once.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Narrows the IR down with `filter`. [`Filter::focus`] needs
+    /// [`Self::enable_edges`].
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Collapses modules this deep below crate roots into single modules.
+    pub fn collapse_depth(mut self, depth: usize) -> Self {
+        self.collapse_depth = Some(depth);
+        self
+    }
+
+    /// Persists parsed files in `dir`, to speed up subsequent builds.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    pub fn build(&self) -> anyhow::Result<Ontology> {
+        self.build_with(&Cache::new(self.cache_dir.clone()))
+    }
+
+    /// Builds the ontology, reusing the files that `cache` has from previous
+    /// builds, e.g., to rebuild it on changes.
+    pub fn build_with(&self, cache: &Cache) -> anyhow::Result<Ontology> {
+        let packages = traverser::traverse(&self.options, cache)?.collect();
+        let mut packages = filter::apply(&self.filter, packages)?;
+        if let Some(depth) = self.collapse_depth {
+            collapse::apply(depth, &mut packages);
+        }
+        Ok(Ontology { packages })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build() {
+        let proj = std::env::temp_dir().join(format!("ontology-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"my-app\"\n"),
+            ("src/lib.rs", "pub mod db;\npub mod api;\n"),
+            ("src/db.rs", "pub struct Pool;\n"),
+            (
+                "src/api.rs",
+                "use crate::db::Pool;\npub fn serve(_: Pool) {}\nfn check() {}\n#[cfg(windows)]\nfn \
+                 pipe() {}\n",
+            ),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let ontology = Ontology::builder()
+            .project(proj.to_str().unwrap())
+            .enable_edges(true)
+            .visibility(Visibility::Private)
+            .cfg(["unix"])
+            .filter(Filter { exclude: vec!["**::db".parse().unwrap()], ..Default::default() })
+            .build();
+        std::fs::remove_dir_all(&proj).unwrap();
+        let packages = ontology.unwrap().into_packages();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].name, "my_app");
+        let lib = &packages[0].crates[0];
+        assert_eq!(lib.items.mods.iter().map(|module| &module.name).collect::<Vec<_>>(), ["api"]);
+        assert_eq!(lib.items.mods[0].deps, Vec::<String>::new());
+        let fns = lib.items.mods[0].items.fns.iter().map(|item| &item.name).collect::<Vec<_>>();
+        assert_eq!(fns, ["serve", "check"]);
+    }
+}
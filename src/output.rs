@@ -1 +1,161 @@
+//! Output formats of the IR, each implemented by an [`OutputBackend`].
+//!
+//! The CLI offers the formats of [`Registry::default`]. Other tools embedding
+//! the library can register their own backends next to the built-in ones.
+
 pub mod cytoscape;
+pub mod html;
+pub mod neo4j;
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use crate::ir::Package;
+
+/// Options shared by all output backends.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// A directory with JavaScript dependencies for `html`, overriding the
+    /// ones embedded at build time.
+    pub assets: Option<PathBuf>,
+    /// CSS colours for the dependency edges of successive crates in the
+    /// Cytoscape-based formats, instead of the built-in ones.
+    pub palette: Vec<String>,
+}
+
+/// An output format.
+pub trait OutputBackend {
+    /// The name of the format in `--format`, e.g., `cytoscape`.
+    fn name(&self) -> &'static str;
+
+    /// A one-line description of the format, for `--help`.
+    fn description(&self) -> &'static str;
+
+    fn write(
+        &self,
+        packages: &[Package],
+        options: &Options,
+        writer: &mut dyn Write,
+    ) -> anyhow::Result<()>;
+
+    /// Writes the output into the file at `path`. Formats consisting of
+    /// several files write them into the directory at `path` instead.
+    fn write_to_path(
+        &self,
+        packages: &[Package],
+        options: &Options,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        // Nothing is written if the output fails.
+        let mut contents = vec![];
+        self.write(packages, options, &mut contents)?;
+        std::fs::write(path, contents).with_context(|| format!("Cannot write {}", path.display()))
+    }
+}
+
+/// Output backends by name.
+pub struct Registry {
+    backends: Vec<Box<dyn OutputBackend>>,
+}
+
+impl Default for Registry {
+    /// Returns the built-in backends.
+    fn default() -> Self {
+        let mut registry = Self { backends: vec![] };
+        registry.register(cytoscape::CytoscapeBackend);
+        registry.register(neo4j::Neo4jBackend);
+        registry.register(neo4j::CypherBackend);
+        registry.register(html::HtmlBackend);
+        registry.register(IrBackend);
+        registry
+    }
+}
+
+impl Registry {
+    /// Adds `backend`, replacing any backend of the same name.
+    pub fn register(&mut self, backend: impl OutputBackend + 'static) {
+        self.backends.retain(|other| other.name() != backend.name());
+        self.backends.push(Box::new(backend));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn OutputBackend> {
+        self.iter().find(|backend| backend.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn OutputBackend> {
+        self.backends.iter().map(AsRef::as_ref)
+    }
+}
+
+/// The IR as JSON, e.g., for `diff`.
+pub struct IrBackend;
+
+impl OutputBackend for IrBackend {
+    fn name(&self) -> &'static str {
+        "ir"
+    }
+
+    fn description(&self) -> &'static str {
+        "The IR as JSON, e.g., for `diff`"
+    }
+
+    fn write(
+        &self,
+        packages: &[Package],
+        _options: &Options,
+        writer: &mut dyn Write,
+    ) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(writer, packages)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Mod;
+
+    struct Names;
+
+    impl OutputBackend for Names {
+        fn name(&self) -> &'static str {
+            "names"
+        }
+
+        fn description(&self) -> &'static str {
+            "Package names"
+        }
+
+        fn write(
+            &self,
+            packages: &[Package],
+            _options: &Options,
+            writer: &mut dyn Write,
+        ) -> anyhow::Result<()> {
+            for package in packages {
+                writeln!(writer, "{}", package.name)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = Registry::default();
+        assert_eq!(
+            registry.iter().map(|backend| backend.name()).collect::<Vec<_>>(),
+            ["cytoscape", "neo4j", "cypher", "html", "ir"]
+        );
+        registry.register(Names);
+        assert!(registry.get("ir").is_some());
+
+        let packages = [Package { name: "app".to_owned(), crates: vec![Mod::new("lib")] }];
+        let mut output = vec![];
+        registry.get("names").unwrap().write(&packages, &Options::default(), &mut output).unwrap();
+        assert_eq!(output, b"app\n");
+    }
+}
@@ -1,9 +1,40 @@
+use std::collections::{HashMap, HashSet};
+
 use displaydoc::Display;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive as _;
 use serde::Serialize;
 
-use crate::ir::{Mod, Package};
+use super::{Options, OutputBackend};
+use crate::{
+    analysis::{coupling, cycles, diff},
+    ir::{id, CodeMetrics, Fn, History, Mod, Package},
+};
+
+/// A Cytoscape.js JSON dump, as consumed by `index.html`.
+pub struct CytoscapeBackend;
+
+impl OutputBackend for CytoscapeBackend {
+    fn name(&self) -> &'static str {
+        "cytoscape"
+    }
+
+    fn description(&self) -> &'static str {
+        "A Cytoscape.js JSON dump, as consumed by `index.html`"
+    }
+
+    fn write(
+        &self,
+        packages: &[Package],
+        options: &Options,
+        writer: &mut dyn std::io::Write,
+    ) -> anyhow::Result<()> {
+        let repr = from_ir_with(packages.iter().cloned(), options);
+        repr.check_ids()?;
+        serde_json::to_writer_pretty(writer, &repr)?;
+        Ok(())
+    }
+}
 
 #[derive(Serialize)]
 #[serde(transparent)]
@@ -11,6 +42,13 @@ pub struct Repr {
     pub elements: Vec<Element>,
 }
 
+impl Repr {
+    /// Fails if two elements have the same ID, which Cytoscape.js rejects.
+    pub fn check_ids(&self) -> anyhow::Result<()> {
+        id::check_unique(self.elements.iter().map(|elem| elem.data.id.as_str()))
+    }
+}
+
 #[derive(Clone, Serialize)]
 pub struct Element {
     pub data: Data,
@@ -30,6 +68,58 @@ pub struct Data {
     pub source: String,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub target: String,
+    /// Coupling metrics of modules and crates, if dependencies were
+    /// extracted.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub coupling: Option<coupling::Coupling>,
+    /// Size and complexity of functions, modules, and crates.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub size: Option<Size>,
+    /// Git history of modules and crates.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub history: Option<History>,
+    /// The number of dependencies an edge stands for, e.g., of collapsed
+    /// modules.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<usize>,
+    /// The colour of an edge from a custom palette, overriding its colour
+    /// class.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Size {
+    pub lines: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fns: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avg_fn_lines: Option<f64>,
+    pub complexity: usize,
+}
+
+impl Size {
+    fn of_fn(item: &Fn) -> Self {
+        Self {
+            lines: item.lines,
+            items: None,
+            fns: None,
+            avg_fn_lines: None,
+            complexity: item.complexity,
+        }
+    }
+
+    fn of_mod(metrics: &CodeMetrics) -> Self {
+        Self {
+            lines: metrics.lines,
+            items: Some(metrics.items),
+            fns: Some(metrics.fns),
+            avg_fn_lines: (metrics.fns > 0).then(|| metrics.fn_lines as f64 / metrics.fns as f64),
+            complexity: metrics.complexity,
+        }
+    }
 }
 
 impl Data {
@@ -44,6 +134,11 @@ impl Data {
             parent: parent.into(),
             source: "".to_owned(),
             target: "".to_owned(),
+            coupling: None,
+            size: None,
+            history: None,
+            weight: None,
+            color: None,
         }
     }
 
@@ -58,17 +153,29 @@ impl Data {
             parent: "".to_owned(),
             source: source.into(),
             target: target.into(),
+            coupling: None,
+            size: None,
+            history: None,
+            weight: None,
+            color: None,
         }
     }
-
-    fn is_edge(&self) -> bool {
-        !self.source.is_empty() && !self.target.is_empty()
-    }
 }
 
 struct Ctx {
     elements: Vec<Element>,
+    // The indices of elements by their IDs.
+    indices: HashMap<String, usize>,
+    // The IDs of the vertices by their paths, to resolve dependencies.
+    vertices: HashMap<String, Vec<String>>,
     color_gen: ColorGenerator,
+    // Custom edge colours, cycled through per crate, if any.
+    palette: Vec<String>,
+    // The number of crates generated so far.
+    crates: usize,
+    // `(module, dependency path)` pairs of edges within dependency cycles.
+    cycle_edges: HashSet<(String, String)>,
+    coupling: Option<coupling::Report>,
 }
 
 #[derive(Default)]
@@ -107,39 +214,99 @@ impl ColorGenerator {
 }
 
 pub fn from_ir(packages: impl Iterator<Item = Package>) -> Repr {
-    let mut ctx = Ctx { elements: vec![], color_gen: Default::default() };
+    from_ir_with(packages, &Options::default())
+}
+
+/// Like [`from_ir`], with the edge colours of `options.palette`, if any.
+pub fn from_ir_with(packages: impl Iterator<Item = Package>, options: &Options) -> Repr {
+    let packages = packages.collect::<Vec<_>>();
+    let cycle_edges = cycles::edge_set(&cycles::find(&packages));
+    // Without `--enable-edges`, there are no dependencies to measure.
+    let mut has_deps = false;
+    for package in &packages {
+        package.walk_mods(&mut |_, module| has_deps |= !module.deps.is_empty());
+    }
+    let coupling = has_deps.then(|| coupling::compute(&packages));
+    let mut vertices = HashMap::<String, Vec<String>>::new();
+    for package in &packages {
+        package.walk_mods(&mut |id, module| {
+            vertices.entry(id.to_owned()).or_default().push(id.to_owned());
+            for item in module.items.named_items() {
+                vertices.entry(item.path(id)).or_default().push(item.id(id));
+            }
+        });
+    }
+    let mut ctx = Ctx {
+        elements: vec![],
+        indices: HashMap::new(),
+        vertices,
+        color_gen: Default::default(),
+        palette: options.palette.clone(),
+        crates: 0,
+        cycle_edges,
+        coupling,
+    };
 
     for package in packages {
         gen_package(&mut ctx, package);
     }
 
-    Repr { elements: remove_invalid_edges(&ctx.elements) }
+    Repr { elements: ctx.elements }
 }
 
-// Removes edges that point to non-existent vertices. This might happen if the
-// traverser encounters paths that it cannot "resolve".
-fn remove_invalid_edges(elements: &[Element]) -> Vec<Element> {
-    elements
-        .iter()
-        .filter(|elem| {
-            if elem.data.is_edge() {
-                let source = &elem.data.source;
-                let target = &elem.data.target;
-
-                return elements.iter().any(|elem| elem.data.id == *source)
-                    && elements.iter().any(|elem| elem.data.id == *target);
+/// Marks the changes of a diff with the `diff-added`, `diff-removed`, and
+/// `diff-changed` classes. Removed vertices and edges are added back if their
+/// parents and endpoints are still there.
+pub fn highlight_diff(repr: &mut Repr, diff: &diff::Diff) {
+    let mut ids = repr.elements.iter().map(|elem| elem.data.id.clone()).collect::<HashSet<_>>();
+
+    for change in &diff.vertices {
+        let class = format!(" diff-{}", change.status);
+        if change.status != diff::Status::Removed {
+            if let Some(elem) = repr.elements.iter_mut().find(|elem| elem.data.id == change.id) {
+                elem.classes.push_str(&class);
             }
+            continue;
+        }
 
-            true
-        })
-        .cloned()
-        .collect()
+        let path = id::path(&change.id);
+        let (parent, name) = path.rsplit_once("::").unwrap_or(("", path));
+        if !parent.is_empty() && !ids.contains(parent) {
+            continue;
+        }
+        let (name, classes) = match change.kind {
+            "package" => (name.to_owned(), format!("vertex-package{class}")),
+            kind => (format!("{kind} {name}"), format!("vertex-{kind} vertex-non-package{class}")),
+        };
+        repr.elements.push(Element { data: Data::new_vertex(&change.id, name, parent), classes });
+        ids.insert(change.id.clone());
+    }
+
+    let mut vertices = HashMap::<&str, Vec<&str>>::new();
+    for id in &ids {
+        vertices.entry(id::path(id)).or_default().push(id);
+    }
+    for change in &diff.edges {
+        let class = format!(" diff-{}", change.status);
+        for &target in vertices.get(change.target.as_str()).into_iter().flatten() {
+            let id = id::edge("dep", &change.source, target);
+            if let Some(elem) = repr.elements.iter_mut().find(|elem| elem.data.id == id) {
+                elem.classes.push_str(&class);
+            } else if ids.contains(&change.source) {
+                repr.elements.push(Element {
+                    data: Data::new_edge(id, &change.source, target),
+                    classes: class.trim_start().to_owned(),
+                });
+            }
+        }
+    }
 }
 
 fn gen_package(ctx: &mut Ctx, package: Package) {
     let package_name = &package.name;
     log::trace!("Generating package {package_name}.");
 
+    ctx.indices.insert(package.name.clone(), ctx.elements.len());
     ctx.elements.push(Element {
         data: Data::new_vertex(&package.name, &package.name, ""),
         classes: "vertex-package".to_owned(),
@@ -152,85 +319,100 @@ fn gen_package(ctx: &mut Ctx, package: Package) {
         log::trace!("Generating crate {crate_name}.");
         log::trace!("{} for {}", ctx.color_gen.current, crate_id);
 
-        gen_vertex(ctx, "crate", crate_name, package_name);
+        let data = gen_vertex(ctx, "crate", &crate_id, crate_name, package_name);
+        data.size = Some(Size::of_mod(&crate_.metrics));
+        data.history = crate_.history.clone();
         gen_module(ctx, crate_, &crate_id);
         ctx.color_gen.update();
+        ctx.crates += 1;
     }
 }
 
 fn gen_module(ctx: &mut Ctx, module: &Mod, parent: &str) {
     for item in &module.items.mods {
-        let name = &item.name;
-        gen_vertex(ctx, "mod", name, parent);
-        gen_module(ctx, item, &format!("{parent}::{name}"));
+        let id = format!("{parent}::{}", item.name);
+        let data = gen_vertex(ctx, "mod", &id, &item.name, parent);
+        data.size = Some(Size::of_mod(&item.metrics));
+        data.history = item.history.clone();
+        gen_module(ctx, item, &id);
     }
-    for item in &module.items.consts {
-        let name = &item.name;
-        gen_vertex(ctx, "const", name, parent);
-    }
-    for item in &module.items.enums {
-        let name = &item.name;
-        gen_vertex(ctx, "enum", name, parent);
+    for item in module.items.named_items() {
+        gen_vertex(ctx, item.kind, &item.id(parent), item.name, parent);
     }
     for item in &module.items.fns {
-        let name = &item.name;
-        gen_vertex(ctx, "fn", name, parent);
-    }
-    for item in &module.items.statics {
-        let name = &item.name;
-        gen_vertex(ctx, "static", name, parent);
+        let index = ctx.indices[&id::item(parent, "fn", &item.name)];
+        ctx.elements[index].data.size = Some(Size::of_fn(item));
     }
-    for item in &module.items.structs {
-        let name = &item.name;
-        gen_vertex(ctx, "struct", name, parent);
-    }
-    for item in &module.items.traits {
-        let name = &item.name;
-        gen_vertex(ctx, "trait", name, parent);
-    }
-    for item in &module.items.trait_aliases {
-        let name = &item.name;
-        gen_vertex(ctx, "trait", name, parent);
-    }
-    for item in &module.items.types {
-        let name = &item.name;
-        gen_vertex(ctx, "type", name, parent);
-    }
-    for item in &module.items.unions {
-        let name = &item.name;
-        gen_vertex(ctx, "union", name, parent);
+    // Repeated dependencies make a single edge.
+    let mut weights = HashMap::<&str, usize>::new();
+    for dep in &module.deps {
+        *weights.entry(dep).or_default() += 1;
     }
     for dep in &module.deps {
-        gen_edge(ctx, parent, dep);
+        let Some(weight) = weights.remove(dep.as_str()) else {
+            continue;
+        };
+        // Dependencies on anything but vertices, e.g., other crates, are
+        // dropped.
+        for target in ctx.vertices.get(dep).cloned().unwrap_or_default() {
+            gen_edge(ctx, parent, dep, &target, weight);
+        }
     }
     // TODO: uses.
 }
 
-fn gen_vertex(ctx: &mut Ctx, kind: &str, name: impl Into<String>, parent: impl Into<String>) {
-    let name = name.into();
-    let parent = parent.into();
+// Pushes a vertex and returns its data. Duplicate IDs are kept, for
+// `Repr::check_ids` to report.
+fn gen_vertex<'a>(
+    ctx: &'a mut Ctx,
+    kind: &str,
+    id: &str,
+    name: &str,
+    parent: &str,
+) -> &'a mut Data {
+    let coupling = ctx.coupling.as_ref().and_then(|coupling| match kind {
+        "crate" => coupling.crates.get(id).cloned(),
+        "mod" => coupling.modules.get(id).cloned(),
+        _ => None,
+    });
 
+    ctx.indices.insert(id.to_owned(), ctx.elements.len());
     ctx.elements.push(Element {
-        data: Data::new_vertex(format!("{parent}::{name}"), format!("{kind} {name}"), parent),
+        data: Data { coupling, ..Data::new_vertex(id, format!("{kind} {name}"), parent) },
         classes: format!("vertex-{kind} vertex-non-package"),
     });
+    &mut ctx.elements.last_mut().expect("Must be just pushed").data
 }
 
-fn gen_edge(ctx: &mut Ctx, source: impl Into<String>, target: impl Into<String>) {
-    let source = source.into();
-    let target = target.into();
+// Pushes a dependency edge for the dependency path `dep` resolved to `target`,
+// or adds `weight` to an existing one.
+fn gen_edge(ctx: &mut Ctx, source: &str, dep: &str, target: &str, weight: usize) {
+    let id = id::edge("dep", source, target);
+    if let Some(&index) = ctx.indices.get(&id) {
+        let data = &mut ctx.elements[index].data;
+        data.weight = Some(data.weight.unwrap_or(1) + weight);
+        return;
+    }
 
     let color = ctx.color_gen.current;
+    let mut classes = format!("edge-{color}");
+    let color =
+        (!ctx.palette.is_empty()).then(|| ctx.palette[ctx.crates % ctx.palette.len()].clone());
+    if ctx.cycle_edges.contains(&(source.to_owned(), dep.to_owned())) {
+        classes.push_str(" edge-cycle");
+    }
 
+    ctx.indices.insert(id.clone(), ctx.elements.len());
     ctx.elements.push(Element {
-        data: Data::new_edge(format!("{source}-{target}"), source, target),
-        classes: format!("edge-{color}"),
+        data: Data { weight: Some(weight), color, ..Data::new_edge(id, source, target) },
+        classes,
     });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::{Fn, Struct, Use, Visibility};
 
     #[test]
     fn color_gen() {
@@ -256,4 +438,82 @@ mod tests {
         gen.update();
         assert_eq!(gen.current, EdgeColor::Blue);
     }
+
+    #[test]
+    fn coupling_needs_deps() {
+        let coupling = |deps: &[&str]| {
+            let mut lib = Mod::new("lib");
+            lib.items.mods.push(Mod::new("a"));
+            let deps = deps.iter().map(|dep| dep.to_string()).collect::<Vec<_>>();
+            lib.items.uses.push(Use {
+                vis: Visibility::Private,
+                repr: "use self::a;".to_owned(),
+                deps: deps.clone(),
+            });
+            lib.deps = deps;
+            let repr = from_ir([Package { name: "pkg".to_owned(), crates: vec![lib] }].into_iter());
+            let module = repr.elements.iter().find(|elem| elem.data.id == "pkg::lib::a").unwrap();
+            module.data.coupling.as_ref().map(|coupling| coupling.afferent)
+        };
+        assert_eq!(coupling(&[]), None);
+        assert_eq!(coupling(&["pkg::lib::a"]), Some(1));
+    }
+
+    #[test]
+    fn collapsed_edge_weights() {
+        let mut tcp = Mod::new("tcp");
+        tcp.deps.extend(["app::lib::runtime::spawn".to_owned(), "app::lib::net::Addr".to_owned()]);
+        let mut net = Mod::new("net");
+        net.deps.extend(["app::lib::runtime::task::Id".to_owned(), "std::io".to_owned()]);
+        net.items.mods.push(tcp);
+        let mut runtime = Mod::new("runtime");
+        runtime.items.mods.push(Mod::new("task"));
+        let mut lib = Mod::new("lib");
+        lib.deps.push("app::lib::net::tcp".to_owned());
+        lib.items.mods.extend([net, runtime]);
+        let mut packages = vec![Package { name: "app".to_owned(), crates: vec![lib] }];
+
+        crate::collapse::apply(1, &mut packages);
+        let repr = from_ir(packages.into_iter());
+        let edges = repr
+            .elements
+            .iter()
+            .filter(|elem| !elem.data.source.is_empty())
+            .map(|elem| (elem.data.id.as_str(), elem.data.weight))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            [
+                ("dep:app::lib::net->app::lib::runtime", Some(2)),
+                ("dep:app::lib->app::lib::net", Some(1))
+            ]
+        );
+    }
+
+    #[test]
+    fn same_names_in_different_namespaces() {
+        let mut lib = Mod::new("lib");
+        lib.items.structs.push(Struct { name: "Foo".to_owned(), ..Default::default() });
+        lib.items.fns.push(Fn { name: "Foo".to_owned(), ..Default::default() });
+        let repr = from_ir([Package { name: "app".to_owned(), crates: vec![lib] }].into_iter());
+
+        assert!(repr.check_ids().is_ok());
+        let ids = repr.elements.iter().map(|elem| elem.data.id.as_str()).collect::<Vec<_>>();
+        assert!(ids.contains(&"app::lib::Foo#struct"));
+        assert!(ids.contains(&"app::lib::Foo#fn"));
+    }
+
+    #[test]
+    fn duplicate_ids() {
+        let repr = from_ir(
+            [Package { name: "app".to_owned(), crates: vec![Mod::new("lib"), Mod::new("lib")] }]
+                .into_iter(),
+        );
+        assert!(repr.check_ids().is_err());
+
+        let mut lib = Mod::new("lib");
+        lib.items.mods.extend([Mod::new("imp"), Mod::new("imp")]);
+        let repr = from_ir([Package { name: "app".to_owned(), crates: vec![lib] }].into_iter());
+        assert!(repr.check_ids().is_err());
+    }
 }
@@ -0,0 +1,130 @@
+//! A self-contained HTML report.
+//!
+//! The report is `index.html` with the Cytoscape dump and the JavaScript
+//! dependencies inlined, so that it can be opened offline, without a static
+//! server. The dependencies are vendored into `vendor/` by `scripts/vendor.sh`
+//! and embedded into the binary at build time; `--assets` overrides them with
+//! the files of another directory.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+
+use super::{cytoscape, Options, OutputBackend};
+use crate::ir::Package;
+
+pub const TEMPLATE: &str = include_str!("../../index.html");
+
+// The expression in `index.html` that loads the dump over HTTP.
+const FETCH_DUMP: &str = r#"fetch("codebase-dump.json", { cache: "no-store" })
+            .then((response) => response.json())"#;
+
+// The scripts loaded by `index.html` from CDNs, paired with their vendored
+// file names and contents. The contents are empty if the files were missing at
+// build time (see `build.rs`).
+const ASSETS: &[(&str, &str, &str)] = &[
+    (
+        "https://cdnjs.cloudflare.com/ajax/libs/cytoscape/3.23.0/cytoscape.min.js",
+        "cytoscape.min.js",
+        include_str!(concat!(env!("OUT_DIR"), "/cytoscape.min.js")),
+    ),
+    (
+        "https://unpkg.com/klayjs@0.4.1/klay.js",
+        "klay.js",
+        include_str!(concat!(env!("OUT_DIR"), "/klay.js")),
+    ),
+    (
+        "https://cdn.jsdelivr.net/gh/cytoscape/cytoscape.js-klay@9fab410e4823268b5fcf877b1f5af2798dd98371/cytoscape-klay.js",
+        "cytoscape-klay.js",
+        include_str!(concat!(env!("OUT_DIR"), "/cytoscape-klay.js")),
+    ),
+];
+
+/// A self-contained HTML report that works offline.
+pub struct HtmlBackend;
+
+impl OutputBackend for HtmlBackend {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn description(&self) -> &'static str {
+        "A self-contained HTML report that works offline"
+    }
+
+    fn write(
+        &self,
+        packages: &[Package],
+        options: &Options,
+        writer: &mut dyn std::io::Write,
+    ) -> anyhow::Result<()> {
+        let repr = cytoscape::from_ir_with(packages.iter().cloned(), options);
+        repr.check_ids()?;
+        writer.write_all(render(&repr, options.assets.as_deref())?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Renders a Cytoscape dump into a single HTML file, inlining the embedded
+/// scripts or, if given, the ones from `assets_dir`.
+pub fn render(repr: &cytoscape::Repr, assets_dir: Option<&Path>) -> anyhow::Result<String> {
+    let mut html = TEMPLATE.to_owned();
+
+    for (url, file_name, embedded) in ASSETS {
+        let script = match assets_dir {
+            Some(dir) => {
+                let path = dir.join(file_name);
+                std::fs::read_to_string(&path)
+                    .with_context(|| format!("Cannot read {}", path.display()))?
+            }
+            None if embedded.is_empty() => bail!(
+                "{file_name} was not vendored at build time; run `scripts/vendor.sh` and rebuild, \
+                 or pass `--assets`"
+            ),
+            None => (*embedded).to_owned(),
+        };
+        html = inline_script(&html, url, &script)?;
+    }
+
+    let dump = serde_json::to_string(repr).expect("Failed to serialize JSON");
+    Ok(html.replacen(FETCH_DUMP, &format!("Promise.resolve({})", escape_script(&dump)), 1))
+}
+
+// Replaces `<script src="{url}"></script>` with an inline script.
+fn inline_script(html: &str, url: &str, script: &str) -> anyhow::Result<String> {
+    let src = html
+        .find(&format!("src=\"{url}\""))
+        .ok_or_else(|| anyhow!("No script {url} in the HTML template"))?;
+    let start = html[..src].rfind("<script").expect("Must be inside a script tag");
+    let end = src + html[src..].find("</script>").expect("Must be a closed script tag");
+
+    Ok(format!("{}<script>{}{}", &html[..start], escape_script(script), &html[end..]))
+}
+
+// Prevents the browser from closing the `<script>` element prematurely, e.g.,
+// at `</ScRiPt>`. `<\/` means the same as `</` in JavaScript strings, regular
+// expressions, and comments.
+fn escape_script(script: &str) -> String {
+    script.replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_is_inlinable() {
+        assert!(TEMPLATE.contains(FETCH_DUMP));
+
+        let mut html = TEMPLATE.to_owned();
+        for (url, file_name, _) in ASSETS {
+            html = inline_script(&html, url, &format!("/* {file_name} */")).unwrap();
+        }
+        for (url, file_name, _) in ASSETS {
+            assert!(!html.contains(url));
+            assert!(html.contains(&format!("<script>/* {file_name} */</script>")));
+        }
+
+        assert_eq!(escape_script(r#""</Script>" + "</div>""#), r#""<\/Script>" + "<\/div>""#);
+    }
+}
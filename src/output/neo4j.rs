@@ -0,0 +1,350 @@
+//! An output for the [Neo4j] graph database.
+//!
+//! Two flavours are supported: node and relationship CSV files for the bulk
+//! [`neo4j-admin database import`] tool, and a plain Cypher script that can be
+//! fed to `cypher-shell`. Vertex IDs follow the [ID scheme](crate::ir::id) of
+//! the Cytoscape output.
+//!
+//! Since `repr` values span multiple lines, the CSV files must be imported
+//! with `--multiline-fields=true`.
+//!
+//! [Neo4j]: https://neo4j.com/
+//! [`neo4j-admin database import`]: https://neo4j.com/docs/operations-manual/current/tools/neo4j-admin/neo4j-admin-import/
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+use displaydoc::Display;
+
+use super::{Options, OutputBackend};
+use crate::ir::{id, Mod, Package};
+
+// The label shared by all nodes, so that they can be indexed by ID.
+const VERTEX_LABEL: &str = "Vertex";
+
+/// Node and relationship CSV files for `neo4j-admin database import`.
+pub struct Neo4jBackend;
+
+impl OutputBackend for Neo4jBackend {
+    fn name(&self) -> &'static str {
+        "neo4j"
+    }
+
+    fn description(&self) -> &'static str {
+        "Node and relationship CSV files for `neo4j-admin database import`"
+    }
+
+    fn write(
+        &self,
+        _packages: &[Package],
+        _options: &Options,
+        _writer: &mut dyn std::io::Write,
+    ) -> anyhow::Result<()> {
+        bail!("`neo4j` writes a directory; use `cypher` for stdout.")
+    }
+
+    fn write_to_path(
+        &self,
+        packages: &[Package],
+        _options: &Options,
+        path: &Path,
+    ) -> anyhow::Result<()> {
+        let graph = from_ir(packages.iter().cloned());
+        graph.check_ids()?;
+        graph.write_csv(path)
+    }
+}
+
+/// A plain Cypher script.
+pub struct CypherBackend;
+
+impl OutputBackend for CypherBackend {
+    fn name(&self) -> &'static str {
+        "cypher"
+    }
+
+    fn description(&self) -> &'static str {
+        "A plain Cypher script"
+    }
+
+    fn write(
+        &self,
+        packages: &[Package],
+        _options: &Options,
+        writer: &mut dyn std::io::Write,
+    ) -> anyhow::Result<()> {
+        let graph = from_ir(packages.iter().cloned());
+        graph.check_ids()?;
+        writer.write_all(graph.to_cypher().as_bytes())?;
+        Ok(())
+    }
+}
+
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub relationships: Vec<Relationship>,
+}
+
+pub struct Node {
+    pub id: String,
+    pub name: String,
+    pub label: String,
+    pub repr: String,
+}
+
+pub struct Relationship {
+    pub start: String,
+    pub end: String,
+    pub kind: RelationshipKind,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug, Display)]
+pub enum RelationshipKind {
+    /// CONTAINS
+    Contains,
+    /// DEPENDS_ON
+    DependsOn,
+}
+
+pub fn from_ir(packages: impl Iterator<Item = Package>) -> Graph {
+    let mut graph = Graph { nodes: vec![], relationships: vec![] };
+
+    for package in packages {
+        gen_package(&mut graph, &package);
+    }
+
+    graph.resolve_dependencies();
+    graph.remove_invalid_relationships();
+    graph
+}
+
+impl Graph {
+    /// Fails if two nodes have the same ID, which the import would reject.
+    pub fn check_ids(&self) -> anyhow::Result<()> {
+        id::check_unique(self.nodes.iter().map(|node| node.id.as_str()))
+    }
+
+    /// Writes `nodes.csv` and `relationships.csv` into `dir`, creating it if
+    /// necessary.
+    pub fn write_csv(&self, dir: impl AsRef<Path>) -> anyhow::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Cannot create directory {}", dir.display()))?;
+
+        let mut nodes = String::from("id:ID,name,repr,:LABEL\n");
+        for node in &self.nodes {
+            let labels = format!("{VERTEX_LABEL};{}", node.label);
+            nodes.push_str(&csv_record(&[&node.id, &node.name, &node.repr, &labels]));
+        }
+
+        let mut relationships = String::from(":START_ID,:END_ID,:TYPE\n");
+        for rel in &self.relationships {
+            relationships.push_str(&csv_record(&[&rel.start, &rel.end, &rel.kind.to_string()]));
+        }
+
+        std::fs::write(dir.join("nodes.csv"), nodes)?;
+        std::fs::write(dir.join("relationships.csv"), relationships)?;
+        Ok(())
+    }
+
+    /// Renders the graph as a Cypher script.
+    pub fn to_cypher(&self) -> String {
+        let mut script = format!(
+            "CREATE CONSTRAINT IF NOT EXISTS FOR (v:{VERTEX_LABEL}) REQUIRE v.id IS UNIQUE;\n"
+        );
+
+        for Node { id, name, label, repr } in &self.nodes {
+            script.push_str(&format!(
+                "CREATE (:{VERTEX_LABEL}:{label} {{id: {}, name: {}, repr: {}}});\n",
+                cypher_string(id),
+                cypher_string(name),
+                cypher_string(repr),
+            ));
+        }
+
+        for Relationship { start, end, kind } in &self.relationships {
+            script.push_str(&format!(
+                "MATCH (a:{VERTEX_LABEL} {{id: {}}}), (b:{VERTEX_LABEL} {{id: {}}}) CREATE \
+                 (a)-[:{kind}]->(b);\n",
+                cypher_string(start),
+                cypher_string(end),
+            ));
+        }
+
+        script
+    }
+
+    // Dependencies end at paths, which name every node in any namespace at
+    // that path, e.g., both a module and a function.
+    fn resolve_dependencies(&mut self) {
+        let mut paths = HashMap::<&str, Vec<&str>>::new();
+        for node in &self.nodes {
+            paths.entry(id::path(&node.id)).or_default().push(&node.id);
+        }
+        self.relationships = std::mem::take(&mut self.relationships)
+            .into_iter()
+            .flat_map(|rel| match (rel.kind, paths.get(rel.end.as_str())) {
+                (RelationshipKind::DependsOn, Some(ids)) => ids
+                    .iter()
+                    .map(|&end| Relationship {
+                        start: rel.start.clone(),
+                        end: end.to_owned(),
+                        kind: rel.kind,
+                    })
+                    .collect(),
+                _ => vec![rel],
+            })
+            .collect();
+    }
+
+    // Removes relationships that point to non-existent nodes, which might
+    // happen if the traverser encounters paths that it cannot "resolve", and
+    // repeated ones, e.g., of repeated dependencies.
+    fn remove_invalid_relationships(&mut self) {
+        let ids = self.nodes.iter().map(|node| node.id.as_str()).collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        self.relationships.retain(|rel| {
+            ids.contains(rel.start.as_str())
+                && ids.contains(rel.end.as_str())
+                && seen.insert((rel.start.clone(), rel.end.clone(), rel.kind))
+        });
+    }
+}
+
+fn gen_package(graph: &mut Graph, package: &Package) {
+    let package_name = &package.name;
+    log::trace!("Generating package {package_name}.");
+
+    gen_node(graph, "package", package_name, package_name, "", "");
+
+    for crate_ in &package.crates {
+        let crate_id = format!("{package_name}::{}", crate_.name);
+        gen_node(graph, "crate", &crate_id, &crate_.name, package_name, "");
+        gen_module(graph, crate_, &crate_id);
+    }
+}
+
+fn gen_module(graph: &mut Graph, module: &Mod, parent: &str) {
+    for item in &module.items.mods {
+        let id = format!("{parent}::{}", item.name);
+        gen_node(graph, "mod", &id, &item.name, parent, "");
+        gen_module(graph, item, &id);
+    }
+    for item in module.items.named_items() {
+        gen_node(graph, item.kind, &item.id(parent), item.name, parent, item.repr);
+    }
+    for dep in &module.deps {
+        graph.relationships.push(Relationship {
+            start: parent.to_owned(),
+            end: dep.clone(),
+            kind: RelationshipKind::DependsOn,
+        });
+    }
+}
+
+// Pushes a node and its `CONTAINS` relationship from `parent`, if any.
+fn gen_node(graph: &mut Graph, kind: &str, id: &str, name: &str, parent: &str, repr: &str) {
+    graph.nodes.push(Node {
+        id: id.to_owned(),
+        name: name.to_owned(),
+        label: label(kind),
+        repr: repr.to_owned(),
+    });
+    if !parent.is_empty() {
+        graph.relationships.push(Relationship {
+            start: parent.to_owned(),
+            end: id.to_owned(),
+            kind: RelationshipKind::Contains,
+        });
+    }
+}
+
+fn label(kind: &str) -> String {
+    let mut chars = kind.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn csv_record(fields: &[&str]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| format!("\"{}\"", field.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    format!("{}\n", fields.join(","))
+}
+
+// JSON string literals are valid Cypher string literals.
+fn cypher_string(s: &str) -> String {
+    serde_json::to_string(s).expect("Failed to serialize a string")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Fn, Struct, Visibility};
+
+    fn packages() -> Vec<Package> {
+        let mut lib = Mod::new("lib");
+        lib.items.fns.push(Fn {
+            name: "foo".to_owned(),
+            vis: Visibility::Public,
+            repr: "pub fn foo() {}".to_owned(),
+            lines: 1,
+            complexity: 1,
+            ..Default::default()
+        });
+        lib.items.structs.push(Struct {
+            name: "Bar".to_owned(),
+            vis: Visibility::Public,
+            repr: "pub struct Bar {\n    x: \"i32\",\n}".to_owned(),
+            ..Default::default()
+        });
+        lib.deps.push("pkg::lib::foo".to_owned());
+        lib.deps.push("std::collections".to_owned());
+
+        vec![Package { name: "pkg".to_owned(), crates: vec![lib] }]
+    }
+
+    #[test]
+    fn nodes_and_relationships() {
+        let graph = from_ir(packages().into_iter());
+
+        let nodes = graph
+            .nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.label.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            nodes,
+            vec![
+                ("pkg", "Package"),
+                ("pkg::lib", "Crate"),
+                ("pkg::lib::foo#fn", "Fn"),
+                ("pkg::lib::Bar#struct", "Struct"),
+            ]
+        );
+
+        let relationships = graph
+            .relationships
+            .iter()
+            .map(|rel| (rel.start.as_str(), rel.end.as_str(), rel.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            relationships,
+            vec![
+                ("pkg", "pkg::lib", RelationshipKind::Contains),
+                ("pkg::lib", "pkg::lib::foo#fn", RelationshipKind::Contains),
+                ("pkg::lib", "pkg::lib::Bar#struct", RelationshipKind::Contains),
+                ("pkg::lib", "pkg::lib::foo#fn", RelationshipKind::DependsOn),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(csv_record(&["a", "b \"c\"\nd"]), "\"a\",\"b \"\"c\"\"\nd\"\n");
+        assert_eq!(cypher_string("x: \"i32\"\n"), r#""x: \"i32\"\n""#);
+    }
+}
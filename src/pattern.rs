@@ -0,0 +1,94 @@
+//! Glob-like patterns over `::`-separated paths, e.g., `pkg::lib::*::tests`.
+//!
+//! Within a segment, `*` matches any sequence of characters, and a whole `**`
+//! segment matches any number of segments. A pattern also matches everything
+//! inside the paths it matches, so `pkg::lib::infra` covers
+//! `pkg::lib::infra::db::Pool`.
+
+use std::{fmt, str::FromStr};
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(from = "String")]
+pub struct PathPattern {
+    segments: Vec<String>,
+}
+
+impl PathPattern {
+    pub fn matches(&self, path: &str) -> bool {
+        match_segments(&self.segments, &path.split("::").collect::<Vec<_>>())
+    }
+}
+
+impl From<String> for PathPattern {
+    fn from(pattern: String) -> Self {
+        Self { segments: pattern.split("::").map(ToOwned::to_owned).collect() }
+    }
+}
+
+impl FromStr for PathPattern {
+    type Err = std::convert::Infallible;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(pattern.to_owned()))
+    }
+}
+
+impl fmt::Display for PathPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join("::"))
+    }
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((segment, pattern)) if segment == "**" => {
+            (0..=path.len()).any(|i| match_segments(pattern, &path[i..]))
+        }
+        Some((segment, pattern)) => match path.split_first() {
+            Some((first, path)) => match_wildcard(segment, first) && match_segments(pattern, path),
+            None => false,
+        },
+    }
+}
+
+fn match_wildcard(pattern: &str, s: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == s,
+        Some((prefix, pattern)) => {
+            let Some(s) = s.strip_prefix(prefix) else {
+                return false;
+            };
+            s.char_indices()
+                .map(|(i, _)| i)
+                .chain([s.len()])
+                .any(|i| match_wildcard(pattern, &s[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches() {
+        let matches = |pattern: &str, path| PathPattern::from(pattern.to_owned()).matches(path);
+
+        assert!(matches("pkg::lib::infra", "pkg::lib::infra"));
+        assert!(matches("pkg::lib::infra", "pkg::lib::infra::db::Pool"));
+        assert!(!matches("pkg::lib::infra", "pkg::lib::infrastructure"));
+        assert!(!matches("pkg::lib::infra", "pkg::lib"));
+
+        assert!(matches("pkg::*::infra", "pkg::main::infra::db"));
+        assert!(matches("pkg::lib::in*ra", "pkg::lib::infra"));
+        assert!(matches("pkg::lib::*_test", "pkg::lib::db_test"));
+        assert!(!matches("pkg::lib::*_test", "pkg::lib::db"));
+
+        assert!(matches("**::tests", "pkg::lib::db::tests::helper"));
+        assert!(matches("pkg::**::db", "pkg::db"));
+        assert!(!matches("**::tests", "pkg::lib::db"));
+    }
+}
@@ -0,0 +1,160 @@
+//! Explicit lists of crate roots, for projects built without Cargo, e.g., with
+//! Bazel or Buck.
+//!
+//! A list has a crate root file per line, relative to the project root,
+//! optionally prefixed with a package name and, after `::`, a crate name:
+//!
+//! ```text
+//! # Empty lines and comments are skipped.
+//! net/src/lib.rs
+//! tools=tools/cli/main.rs
+//! tools::lint=tools/lint/check.rs
+//! ```
+//!
+//! The package name defaults to the name of the directory with the file, or
+//! its parent for `src`, and the crate name to the file stem, e.g., `lib` for
+//! `lib.rs`. Two roots must not make the same crate of the same package.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::{anyhow, bail, Context};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CrateRoot {
+    pub package: String,
+    /// The crate name, e.g., `main` or `lib`.
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl CrateRoot {
+    /// Creates a crate root, with the default package and crate names unless
+    /// they are given.
+    pub fn new(package: Option<&str>, name: Option<&str>, path: PathBuf) -> Self {
+        let package = package.map(ToOwned::to_owned).unwrap_or_else(|| {
+            let dir = package_dir(&path);
+            dir.file_name().unwrap_or_default().to_string_lossy().into_owned()
+        });
+        let name = name
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+        Self { package: package.replace('-', "_"), name: name.replace('-', "_"), path }
+    }
+}
+
+/// Reads a list from a file or, for `-`, from stdin.
+pub fn load(source: &str, proj: &Path) -> anyhow::Result<Vec<CrateRoot>> {
+    // Stdin can only be read once, but `--watch` reloads the project.
+    static STDIN: OnceLock<Result<String, String>> = OnceLock::new();
+
+    let list = match source {
+        "-" => STDIN
+            .get_or_init(|| std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string()))
+            .clone()
+            .map_err(|e| anyhow!("Cannot read stdin: {e}"))?,
+        path => std::fs::read_to_string(path).with_context(|| format!("Cannot open {path}"))?,
+    };
+    parse(&list, proj)
+}
+
+pub fn parse(list: &str, proj: &Path) -> anyhow::Result<Vec<CrateRoot>> {
+    let roots = list
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (prefix, path) = match line.split_once('=') {
+                Some((prefix, path)) => (Some(prefix.trim()), path.trim()),
+                None => (None, line),
+            };
+            let (package, name) = match prefix.and_then(|prefix| prefix.split_once("::")) {
+                Some((package, name)) => (Some(package), Some(name)),
+                None => (prefix, None),
+            };
+            CrateRoot::new(package, name, proj.join(path))
+        })
+        .collect::<Vec<_>>();
+    check_unique(&roots)?;
+    Ok(roots)
+}
+
+/// Fails if two roots make the same crate of the same package, which would
+/// make their IDs collide.
+pub fn check_unique(roots: &[CrateRoot]) -> anyhow::Result<()> {
+    for (i, root) in roots.iter().enumerate() {
+        let same =
+            |other: &&CrateRoot| (&other.package, &other.name) == (&root.package, &root.name);
+        if let Some(other) = roots[..i].iter().find(same) {
+            bail!(
+                "Both {} and {} are crate `{}::{}`; name one of them with a `package::crate=` \
+                 prefix",
+                other.path.display(),
+                root.path.display(),
+                root.package,
+                root.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the package directories of `roots`, like workspace members.
+pub fn members(roots: &[CrateRoot]) -> Vec<PathBuf> {
+    let mut members = roots.iter().map(|root| package_dir(&root.path)).collect::<Vec<_>>();
+    members.sort();
+    members.dedup();
+    members
+}
+
+fn package_dir(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    match dir.file_name() {
+        Some(name) if name == "src" => dir.parent().unwrap_or(dir).to_owned(),
+        _ => dir.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_roots() {
+        let roots = parse(
+            "# Bazel targets\n\nmy-net/src/lib.rs\ntools = tools/cli/main.rs\nutil/bits.rs\n\
+             tools::my-lint = tools/lint/check.rs\n",
+            Path::new("/repo"),
+        )
+        .unwrap();
+        let root = |package: &str, name: &str, path: &str| CrateRoot {
+            package: package.to_owned(),
+            name: name.to_owned(),
+            path: PathBuf::from(path),
+        };
+        assert_eq!(
+            roots,
+            vec![
+                root("my_net", "lib", "/repo/my-net/src/lib.rs"),
+                root("tools", "main", "/repo/tools/cli/main.rs"),
+                root("util", "bits", "/repo/util/bits.rs"),
+                root("tools", "my_lint", "/repo/tools/lint/check.rs"),
+            ]
+        );
+        assert_eq!(
+            members(&roots),
+            vec![
+                PathBuf::from("/repo/my-net"),
+                PathBuf::from("/repo/tools/cli"),
+                PathBuf::from("/repo/tools/lint"),
+                PathBuf::from("/repo/util")
+            ]
+        );
+
+        let duplicate = parse("net/src/lib.rs\nnet/lib.rs\n", Path::new("/repo")).unwrap_err();
+        assert!(duplicate.to_string().contains("crate `net::lib`"));
+        assert!(parse("net/src/lib.rs\nnet::legacy=net/lib.rs\n", Path::new("/repo")).is_ok());
+    }
+}
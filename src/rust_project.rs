@@ -0,0 +1,197 @@
+//! Projects described by rust-analyzer's [`rust-project.json`], as generated
+//! for Bazel, Buck, and hand-rolled builds.
+//!
+//! Every workspace member crate is traversed from its root module, except for
+//! test targets. Crates are grouped into packages by their display names, and
+//! named `lib` for library targets and after their root files otherwise, as in
+//! [`crate::roots`]. Dependencies on crates under other names, e.g., renamed
+//! ones, are resolved to the packages of those crates. Editions and cfgs are
+//! not used; `#[cfg]` is only evaluated with the options of the traversal.
+//!
+//! [`rust-project.json`]: https://rust-analyzer.github.io/book/non_cargo_based_projects.html
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::{
+    ir::{id, Mod, Package},
+    roots::{self, CrateRoot},
+};
+
+#[derive(Deserialize)]
+struct RustProject {
+    crates: Vec<Crate>,
+}
+
+#[derive(Deserialize)]
+struct Crate {
+    display_name: Option<String>,
+    root_module: String,
+    #[serde(default)]
+    deps: Vec<Dep>,
+    #[serde(default = "default_is_workspace_member")]
+    is_workspace_member: bool,
+    build: Option<Build>,
+}
+
+#[derive(Deserialize)]
+struct Dep {
+    #[serde(rename = "crate")]
+    crate_: usize,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Build {
+    target_kind: Option<String>,
+}
+
+fn default_is_workspace_member() -> bool {
+    true
+}
+
+/// The crates of a project and their dependency names.
+pub struct Project {
+    pub roots: Vec<CrateRoot>,
+    // For every root, the names of its dependencies that differ from their
+    // package names, mapped to the latter.
+    renames: Vec<HashMap<String, String>>,
+}
+
+/// Reads a `rust-project.json` file. Relative paths in it are relative to the
+/// file.
+pub fn load(path: &Path) -> anyhow::Result<Project> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let project = serde_json::from_str::<RustProject>(&contents)
+        .with_context(|| format!("Cannot parse {}", path.display()))?;
+    resolve(project, path.parent().unwrap_or(Path::new("")))
+}
+
+fn resolve(project: RustProject, dir: &Path) -> anyhow::Result<Project> {
+    let crate_roots = project
+        .crates
+        .iter()
+        .map(|crate_| {
+            // Library crates are `lib`, whatever their root files; others are
+            // named after the file, e.g., `main`.
+            let target_kind = crate_.build.as_ref().and_then(|build| build.target_kind.as_deref());
+            let name = (target_kind == Some("lib")).then_some(id::LIB);
+            CrateRoot::new(crate_.display_name.as_deref(), name, dir.join(&crate_.root_module))
+        })
+        .collect::<Vec<_>>();
+
+    let mut roots = vec![];
+    let mut renames = vec![];
+    for (crate_, root) in project.crates.iter().zip(crate_roots.iter().cloned()) {
+        let target_kind = crate_.build.as_ref().and_then(|build| build.target_kind.as_deref());
+        if !crate_.is_workspace_member || target_kind == Some("test") {
+            continue;
+        }
+
+        renames.push(
+            crate_
+                .deps
+                .iter()
+                .filter_map(|dep| {
+                    let package = &crate_roots.get(dep.crate_)?.package;
+                    (*package != dep.name).then(|| (dep.name.clone(), package.clone()))
+                })
+                .collect(),
+        );
+        roots.push(root);
+    }
+    roots::check_unique(&roots)?;
+    Ok(Project { roots, renames })
+}
+
+impl Project {
+    /// Rewrites the dependencies of traversed `packages` on renamed crates,
+    /// e.g., `net::lib::Socket` for a dependency named `net` on a crate
+    /// `my_net`, into `my_net::lib::Socket`.
+    pub fn resolve_deps(&self, packages: &mut [Package]) {
+        for package in packages {
+            for crate_ in &mut package.crates {
+                let Some(renames) = self
+                    .roots
+                    .iter()
+                    .position(|root| root.package == package.name && root.name == crate_.name)
+                    .map(|i| &self.renames[i])
+                else {
+                    continue;
+                };
+                if !renames.is_empty() {
+                    rename_deps(crate_, renames);
+                }
+            }
+        }
+    }
+}
+
+fn rename_deps(module: &mut Mod, renames: &HashMap<String, String>) {
+    let rename = |dep: &mut String| {
+        let Some((name, rest)) = dep.split_once("::") else {
+            return;
+        };
+        if let Some(package) = renames.get(name) {
+            *dep = format!("{package}::{rest}");
+        }
+    };
+    module.deps.iter_mut().for_each(rename);
+    for use_ in &mut module.items.uses {
+        use_.deps.iter_mut().for_each(rename);
+    }
+    for submodule in &mut module.items.mods {
+        rename_deps(submodule, renames);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_project() {
+        let project = serde_json::from_str::<RustProject>(
+            r#"{
+                "crates": [
+                    {"display_name": "my-net", "root_module": "net/src/lib.rs", "edition": "2021", "deps": []},
+                    {"display_name": "my-net", "root_module": "net/src/lib.rs", "deps": [], "build": {"label": "//net:test", "build_file": "net/BUILD", "target_kind": "test"}},
+                    {"root_module": "tools/cli/main.rs", "deps": [{"crate": 0, "name": "net"}, {"crate": 3, "name": "regex"}]},
+                    {"display_name": "regex", "root_module": "/registry/regex/src/lib.rs", "is_workspace_member": false},
+                    {"display_name": "cli", "root_module": "tools/cli/lint.rs", "build": {"label": "//tools:lint", "build_file": "tools/BUILD", "target_kind": "bin"}},
+                    {"display_name": "cli", "root_module": "tools/cli/cli.rs", "build": {"label": "//tools:cli", "build_file": "tools/BUILD", "target_kind": "lib"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let project = resolve(project, Path::new("/repo")).unwrap();
+        assert_eq!(
+            project
+                .roots
+                .iter()
+                .map(|root| format!("{}::{}", root.package, root.name))
+                .collect::<Vec<_>>(),
+            vec!["my_net::lib", "cli::main", "cli::lint", "cli::lib"]
+        );
+
+        let mut cli = Mod::new("main");
+        cli.deps.extend(["net::lib::Socket".to_owned(), "regex::lib::Regex".to_owned()]);
+        let mut packages = vec![Package { name: "cli".to_owned(), crates: vec![cli] }];
+        project.resolve_deps(&mut packages);
+        assert_eq!(packages[0].crates[0].deps, vec!["my_net::lib::Socket", "regex::lib::Regex"]);
+
+        let duplicate = serde_json::from_str::<RustProject>(
+            r#"{
+                "crates": [
+                    {"display_name": "net", "root_module": "net/src/lib.rs"},
+                    {"display_name": "net", "root_module": "net/v2/lib.rs"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(resolve(duplicate, Path::new("/repo")).is_err());
+    }
+}
@@ -1,61 +1,476 @@
 //! Utilities related to working with Rust's CST (Concrete Syntax Tree).
 
+use std::{collections::BTreeSet, path::PathBuf};
+
 use quote::ToTokens;
+use syn::{
+    punctuated::Punctuated,
+    visit::{self, Visit},
+};
+
+use crate::ir::{
+    AssocConst, AssocFn, AssocItems, AssocType, CodeMetrics, Field, GenericParam, GenericParamKind,
+    Generics, Param, Signature, Variant, Visibility, WherePredicate,
+};
+
+/// The visibility of an item, or `Private` for items without one, e.g.,
+/// implementations.
+pub fn item_visibility(item: &syn::Item) -> Visibility {
+    match item {
+        syn::Item::Const(item) => visibility(&item.vis),
+        syn::Item::Enum(item) => visibility(&item.vis),
+        syn::Item::Fn(item) => visibility(&item.vis),
+        syn::Item::Mod(item) => visibility(&item.vis),
+        syn::Item::Static(item) => visibility(&item.vis),
+        syn::Item::Struct(item) => visibility(&item.vis),
+        syn::Item::Trait(item) => visibility(&item.vis),
+        syn::Item::TraitAlias(item) => visibility(&item.vis),
+        syn::Item::Type(item) => visibility(&item.vis),
+        syn::Item::Union(item) => visibility(&item.vis),
+        syn::Item::Use(item) => visibility(&item.vis),
+        _ => Visibility::Private,
+    }
+}
+
+pub fn item_attrs(item: &syn::Item) -> &[syn::Attribute] {
+    match item {
+        syn::Item::Const(item) => &item.attrs,
+        syn::Item::Enum(item) => &item.attrs,
+        syn::Item::ExternCrate(item) => &item.attrs,
+        syn::Item::Fn(item) => &item.attrs,
+        syn::Item::ForeignMod(item) => &item.attrs,
+        syn::Item::Impl(item) => &item.attrs,
+        syn::Item::Macro(item) => &item.attrs,
+        syn::Item::Mod(item) => &item.attrs,
+        syn::Item::Static(item) => &item.attrs,
+        syn::Item::Struct(item) => &item.attrs,
+        syn::Item::Trait(item) => &item.attrs,
+        syn::Item::TraitAlias(item) => &item.attrs,
+        syn::Item::Type(item) => &item.attrs,
+        syn::Item::Union(item) => &item.attrs,
+        syn::Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// Whether all `#[cfg]` attributes in `attrs` hold when exactly the options in
+/// `cfg` are set, e.g., `unix` or `feature = "serde"`. Predicates that cannot
+/// be parsed are taken to hold.
+pub fn is_cfg_enabled(attrs: &[syn::Attribute], cfg: &[String]) -> bool {
+    // Options are compared in their token form, which ignores whitespace.
+    let options = cfg
+        .iter()
+        .filter_map(|option| syn::parse_str::<syn::Meta>(option).ok())
+        .map(|option| option.to_token_stream().to_string())
+        .collect::<Vec<_>>();
+    attrs.iter().filter(|attr| attr.path().is_ident("cfg")).all(|attr| {
+        attr.parse_args::<syn::Meta>().map_or(true, |predicate| cfg_holds(&predicate, &options))
+    })
+}
+
+fn cfg_holds(predicate: &syn::Meta, options: &[String]) -> bool {
+    let syn::Meta::List(list) = predicate else {
+        return options.contains(&predicate.to_token_stream().to_string());
+    };
+    let Ok(args) = list.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+    else {
+        return true;
+    };
+    match list.path.get_ident().map(ToString::to_string).as_deref() {
+        Some("all") => args.iter().all(|arg| cfg_holds(arg, options)),
+        Some("any") => args.iter().any(|arg| cfg_holds(arg, options)),
+        Some("not") => !args.first().is_none_or(|arg| cfg_holds(arg, options)),
+        _ => true,
+    }
+}
+
+pub fn visibility(vis: &syn::Visibility) -> Visibility {
+    match vis {
+        syn::Visibility::Public(_) => Visibility::Public,
+        syn::Visibility::Restricted(_) => Visibility::Restricted,
+        syn::Visibility::Inherited => Visibility::Private,
+    }
+}
+
+pub fn generics(generics: &syn::Generics) -> Generics {
+    let params = generics.params.iter().map(|param| match param {
+        syn::GenericParam::Lifetime(param) => GenericParam {
+            name: param.lifetime.to_string(),
+            kind: GenericParamKind::Lifetime,
+            bounds: param.bounds.iter().map(compact_tokens).collect(),
+            ty: None,
+            default: None,
+        },
+        syn::GenericParam::Type(param) => GenericParam {
+            name: param.ident.to_string(),
+            kind: GenericParamKind::Type,
+            bounds: param.bounds.iter().map(compact_tokens).collect(),
+            ty: None,
+            default: param.default.as_ref().map(compact_tokens),
+        },
+        syn::GenericParam::Const(param) => GenericParam {
+            name: param.ident.to_string(),
+            kind: GenericParamKind::Const,
+            bounds: vec![],
+            ty: Some(compact_tokens(&param.ty)),
+            default: param.default.as_ref().map(compact_tokens),
+        },
+    });
+    let predicates = generics.where_clause.iter().flat_map(|clause| &clause.predicates);
+    let where_clause = predicates.filter_map(|predicate| match predicate {
+        syn::WherePredicate::Lifetime(predicate) => Some(WherePredicate {
+            bounded: predicate.lifetime.to_string(),
+            bounds: predicate.bounds.iter().map(compact_tokens).collect(),
+        }),
+        syn::WherePredicate::Type(predicate) => Some(WherePredicate {
+            bounded: compact_tokens(&predicate.bounded_ty),
+            bounds: predicate.bounds.iter().map(compact_tokens).collect(),
+        }),
+        _ => None,
+    });
+    Generics { params: params.collect(), where_clause: where_clause.collect() }
+}
+
+pub fn field(field: &syn::Field) -> Field {
+    Field {
+        name: field.ident.as_ref().map(ToString::to_string),
+        vis: visibility(&field.vis),
+        ty: compact_tokens(&field.ty),
+    }
+}
+
+pub fn variant(variant: &syn::Variant) -> Variant {
+    Variant {
+        name: variant.ident.to_string(),
+        // Fields of variants are as visible as their enum.
+        fields: variant
+            .fields
+            .iter()
+            .map(|variant_field| Field { vis: Visibility::Public, ..field(variant_field) })
+            .collect(),
+        discriminant: variant.discriminant.as_ref().map(|(_, expr)| compact_tokens(expr)),
+    }
+}
+
+pub fn signature(sig: &syn::Signature) -> Signature {
+    let params = sig.inputs.iter().map(|param| match param {
+        syn::FnArg::Receiver(receiver) => {
+            Param { pat: "self".to_owned(), ty: compact_tokens(&receiver.ty) }
+        }
+        syn::FnArg::Typed(param) => {
+            Param { pat: compact_tokens(&param.pat), ty: compact_tokens(&param.ty) }
+        }
+    });
+    Signature {
+        generics: generics(&sig.generics),
+        params: params.collect(),
+        output: match &sig.output {
+            syn::ReturnType::Default => None,
+            syn::ReturnType::Type(_, ty) => Some(compact_tokens(ty)),
+        },
+        is_async: sig.asyncness.is_some(),
+        is_const: sig.constness.is_some(),
+        is_unsafe: sig.unsafety.is_some(),
+    }
+}
+
+pub fn trait_items(items: &[syn::TraitItem]) -> AssocItems {
+    let mut acc = AssocItems::default();
+    for item in items {
+        match item {
+            syn::TraitItem::Const(item) => acc.consts.push(AssocConst {
+                name: item.ident.to_string(),
+                vis: Visibility::Public,
+                ty: compact_tokens(&item.ty),
+                has_value: item.default.is_some(),
+            }),
+            syn::TraitItem::Fn(item) => acc.fns.push(AssocFn {
+                name: item.sig.ident.to_string(),
+                vis: Visibility::Public,
+                sig: signature(&item.sig),
+                has_body: item.default.is_some(),
+            }),
+            syn::TraitItem::Type(item) => acc.types.push(AssocType {
+                name: item.ident.to_string(),
+                vis: Visibility::Public,
+                generics: generics(&item.generics),
+                bounds: item.bounds.iter().map(compact_tokens).collect(),
+                ty: item.default.as_ref().map(|(_, ty)| compact_tokens(ty)),
+            }),
+            _ => {}
+        }
+    }
+    acc
+}
+
+/// Returns the items of an `impl` block, skipping the items of inherent
+/// implementations less visible than `level`.
+pub fn impl_items(items: &[syn::ImplItem], is_trait_impl: bool, level: Visibility) -> AssocItems {
+    let vis = |vis| match visibility(vis) {
+        _ if is_trait_impl => Some(Visibility::Public),
+        vis => vis.is_at_least(level).then_some(vis),
+    };
+
+    let mut acc = AssocItems::default();
+    for item in items {
+        match item {
+            syn::ImplItem::Const(item) => acc.consts.extend(vis(&item.vis).map(|vis| AssocConst {
+                name: item.ident.to_string(),
+                vis,
+                ty: compact_tokens(&item.ty),
+                has_value: true,
+            })),
+            syn::ImplItem::Fn(item) => acc.fns.extend(vis(&item.vis).map(|vis| AssocFn {
+                name: item.sig.ident.to_string(),
+                vis,
+                sig: signature(&item.sig),
+                has_body: true,
+            })),
+            syn::ImplItem::Type(item) => acc.types.extend(vis(&item.vis).map(|vis| AssocType {
+                name: item.ident.to_string(),
+                vis,
+                generics: generics(&item.generics),
+                bounds: vec![],
+                ty: Some(compact_tokens(&item.ty)),
+            })),
+            _ => {}
+        }
+    }
+    acc
+}
+
+/// A path imported by a `use` item.
+#[derive(Debug, Eq, PartialEq)]
+pub struct UsePath {
+    /// The imported path, e.g., `["foo", "bar"]` for `use foo::bar as baz;`,
+    /// or the module of a glob import.
+    pub segments: Vec<String>,
+    /// The name the path is imported as, e.g., `baz`, or `None` for globs.
+    pub name: Option<String>,
+}
+
+/// Returns the paths imported by a use tree.
+pub fn flatten_use_tree(tree: &syn::UseTree) -> Vec<UsePath> {
+    fn flatten(tree: &syn::UseTree, prefix: &mut Vec<String>, acc: &mut Vec<UsePath>) {
+        match tree {
+            syn::UseTree::Path(path) => {
+                prefix.push(path.ident.to_string());
+                flatten(&path.tree, prefix, acc);
+                prefix.pop();
+            }
+            // `foo::{self}` imports `foo` itself.
+            syn::UseTree::Name(name) if name.ident == "self" => {
+                acc.push(UsePath { segments: prefix.clone(), name: prefix.last().cloned() })
+            }
+            syn::UseTree::Name(name) => acc.push(UsePath {
+                segments: [&prefix[..], &[name.ident.to_string()]].concat(),
+                name: Some(name.ident.to_string()),
+            }),
+            syn::UseTree::Rename(rename) => acc.push(UsePath {
+                segments: [&prefix[..], &[rename.ident.to_string()]].concat(),
+                name: Some(rename.rename.to_string()),
+            }),
+            syn::UseTree::Glob(_) => acc.push(UsePath { segments: prefix.clone(), name: None }),
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    flatten(tree, prefix, acc);
+                }
+            }
+        }
+    }
+
+    let mut acc = vec![];
+    flatten(tree, &mut vec![], &mut acc);
+    acc.retain(|path| !path.segments.is_empty());
+    acc
+}
+
+/// Returns the name an item declares in the type or value namespace, if any.
+pub fn item_ident(item: &syn::Item) -> Option<&syn::Ident> {
+    match item {
+        syn::Item::Const(item) => Some(&item.ident),
+        syn::Item::Enum(item) => Some(&item.ident),
+        syn::Item::Fn(item) => Some(&item.sig.ident),
+        syn::Item::Mod(item) => Some(&item.ident),
+        syn::Item::Static(item) => Some(&item.ident),
+        syn::Item::Struct(item) => Some(&item.ident),
+        syn::Item::Trait(item) => Some(&item.ident),
+        syn::Item::TraitAlias(item) => Some(&item.ident),
+        syn::Item::Type(item) => Some(&item.ident),
+        syn::Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
+
+/// Renders tokens without the spaces that `to_string` puts between them, e.g.,
+/// `Vec<&'a str>` instead of `Vec < & 'a str >`.
+pub fn compact_tokens(node: impl ToTokens) -> String {
+    let spaced = node.to_token_stream().to_string();
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut compact = String::with_capacity(spaced.len());
+    let mut chars = spaced.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ' ' || (is_word(compact.chars().last()) && is_word(chars.peek().copied())) {
+            compact.push(c);
+        }
+    }
+    compact
+}
+
+/// Returns the traits listed in `#[derive(...)]` attributes, as written.
+pub fn derived_traits(attrs: &[syn::Attribute]) -> Vec<String> {
+    let parser = Punctuated::<syn::Path, syn::Token![,]>::parse_terminated;
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| attr.parse_args_with(parser).ok())
+        .flat_map(|paths| paths.into_iter().map(compact_tokens))
+        .collect()
+}
+
+/// Returns the names brought into scope by a `use` item, given by its `repr`.
+pub fn reexported_names(repr: &str) -> impl Iterator<Item = String> {
+    syn::parse_str::<syn::ItemUse>(repr)
+        .map(|item| flatten_use_tree(&item.tree))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|path| path.name)
+}
+
+/// Keeps only the attributes that affect the public API, i.e.,
+/// `#[non_exhaustive]`.
+pub fn api_attrs(attrs: Vec<syn::Attribute>) -> Vec<syn::Attribute> {
+    attrs.into_iter().filter(|attr| attr.path().is_ident("non_exhaustive")).collect()
+}
 
-pub fn is_public_item(item: &syn::Item) -> bool {
-    let is_public = |vis| !matches!(vis, &syn::Visibility::Inherited);
+/// Returns the value of the `#[path = "..."]` attribute, if any.
+pub fn path_attr(attrs: &[syn::Attribute]) -> Option<PathBuf> {
+    attrs.iter().filter(|attr| attr.path().is_ident("path")).find_map(|attr| match &attr.meta {
+        syn::Meta::NameValue(syn::MetaNameValue {
+            value: syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(path), .. }),
+            ..
+        }) => Some(PathBuf::from(path.value())),
+        _ => None,
+    })
+}
+
+/// Adds the size and complexity of an item to `metrics`. The lines of code are
+/// accounted separately, per file.
+pub fn measure_item(item: &syn::Item, metrics: &mut CodeMetrics) {
+    let mut measure_fn = |sig: &syn::Signature, block: &syn::Block| {
+        metrics.fns += 1;
+        metrics.fn_lines += fn_lines(sig, block);
+        metrics.complexity += cyclomatic_complexity(block);
+    };
 
     match item {
-        syn::Item::Const(item) => is_public(&item.vis),
-        syn::Item::Enum(item) => is_public(&item.vis),
-        syn::Item::Fn(item) => is_public(&item.vis),
-        syn::Item::Mod(item) => is_public(&item.vis),
-        syn::Item::Static(item) => is_public(&item.vis),
-        syn::Item::Struct(item) => is_public(&item.vis),
-        syn::Item::Trait(item) => is_public(&item.vis),
-        syn::Item::TraitAlias(item) => is_public(&item.vis),
-        syn::Item::Type(item) => is_public(&item.vis),
-        syn::Item::Union(item) => is_public(&item.vis),
-        syn::Item::Use(item) => is_public(&item.vis),
-        _ => false,
-    }
-}
-
-pub fn format_path(segments: Vec<syn::PathSegment>) -> String {
-    syn::Path { leading_colon: None, segments: syn::punctuated::Punctuated::from_iter(segments) }
-        .to_token_stream()
-        .to_string()
-        .replace(' ', "")
-}
-
-pub fn path_segment(ident: &str) -> syn::PathSegment {
-    syn::PathSegment::from(syn::Ident::new(ident, proc_macro2::Span::call_site()))
-}
-
-pub fn flatten_use_tree(tree: &syn::UseTree) -> Vec<syn::Path> {
-    match tree {
-        syn::UseTree::Path(syn::UsePath { ident, tree, .. }) => {
-            let mut uses = flatten_use_tree(tree);
-            for use_ in &mut uses {
-                use_.segments.insert(
-                    0,
-                    syn::PathSegment { ident: ident.clone(), arguments: syn::PathArguments::None },
-                );
+        syn::Item::Fn(item) => measure_fn(&item.sig, &item.block),
+        syn::Item::Impl(item) => {
+            for item in &item.items {
+                if let syn::ImplItem::Fn(item) = item {
+                    measure_fn(&item.sig, &item.block);
+                }
+            }
+        }
+        syn::Item::Trait(item) => {
+            for item in &item.items {
+                if let syn::TraitItem::Fn(syn::TraitItemFn { sig, default: Some(block), .. }) = item
+                {
+                    measure_fn(sig, block);
+                }
+            }
+        }
+        _ => {}
+    }
+    metrics.items += 1;
+}
+
+/// Returns the number of lines from the signature to the closing brace.
+pub fn fn_lines(sig: &syn::Signature, block: &syn::Block) -> usize {
+    lines_between(sig.fn_token.span, block.brace_token.span.close())
+}
+
+/// Returns the number of lines spanned by `start` through `end`.
+pub fn lines_between(start: proc_macro2::Span, end: proc_macro2::Span) -> usize {
+    (end.end().line + 1).saturating_sub(start.start().line)
+}
+
+/// Computes the [cyclomatic complexity] of a function body: one plus the
+/// number of branches (`if`, `while`, `for`, extra `match` arms, match
+/// guards, `&&`, and `||`). Nested items are not taken into account.
+///
+/// [cyclomatic complexity]: https://en.wikipedia.org/wiki/Cyclomatic_complexity
+pub fn cyclomatic_complexity(block: &syn::Block) -> usize {
+    #[derive(Default)]
+    struct Branches(usize);
+
+    impl<'ast> Visit<'ast> for Branches {
+        fn visit_expr_if(&mut self, expr: &'ast syn::ExprIf) {
+            self.0 += 1;
+            visit::visit_expr_if(self, expr);
+        }
+
+        fn visit_expr_while(&mut self, expr: &'ast syn::ExprWhile) {
+            self.0 += 1;
+            visit::visit_expr_while(self, expr);
+        }
+
+        fn visit_expr_for_loop(&mut self, expr: &'ast syn::ExprForLoop) {
+            self.0 += 1;
+            visit::visit_expr_for_loop(self, expr);
+        }
+
+        fn visit_expr_match(&mut self, expr: &'ast syn::ExprMatch) {
+            self.0 += expr.arms.len().saturating_sub(1);
+            self.0 += expr.arms.iter().filter(|arm| arm.guard.is_some()).count();
+            visit::visit_expr_match(self, expr);
+        }
+
+        fn visit_expr_binary(&mut self, expr: &'ast syn::ExprBinary) {
+            if matches!(expr.op, syn::BinOp::And(_) | syn::BinOp::Or(_)) {
+                self.0 += 1;
+            }
+            visit::visit_expr_binary(self, expr);
+        }
+
+        fn visit_item(&mut self, _item: &'ast syn::Item) {}
+    }
+
+    let mut branches = Branches::default();
+    branches.visit_block(block);
+    1 + branches.0
+}
+
+/// Adds the paths with several segments that an item refers to, as written,
+/// e.g., `ir::Mod::new`, to `acc`. Nested modules and `use` items are skipped.
+pub fn collect_paths(item: &syn::Item, acc: &mut BTreeSet<String>) {
+    struct Paths<'a>(&'a mut BTreeSet<String>);
+
+    impl<'ast> Visit<'ast> for Paths<'_> {
+        fn visit_path(&mut self, path: &'ast syn::Path) {
+            if path.segments.len() > 1 {
+                let segments = path.segments.iter().map(|segment| segment.ident.to_string());
+                self.0.insert(segments.collect::<Vec<_>>().join("::"));
             }
-            uses
+            visit::visit_path(self, path);
         }
-        syn::UseTree::Group(syn::UseGroup { items, .. }) => {
-            items.iter().flat_map(flatten_use_tree).collect()
+
+        // Arguments of function-like macros, e.g., `println!`, are often
+        // expressions.
+        fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+            let parser = Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated;
+            if let Ok(exprs) = mac.parse_body_with(parser) {
+                exprs.iter().for_each(|expr| self.visit_expr(expr));
+            }
+            visit::visit_macro(self, mac);
         }
-        syn::UseTree::Name(syn::UseName { ident, .. }) => vec![syn::Path {
-            leading_colon: None,
-            segments: syn::punctuated::Punctuated::from_iter(vec![syn::PathSegment {
-                ident: ident.clone(),
-                arguments: syn::PathArguments::None,
-            }]),
-        }],
-        _ => vec![],
+
+        fn visit_item_mod(&mut self, _item: &'ast syn::ItemMod) {}
+
+        fn visit_item_use(&mut self, _item: &'ast syn::ItemUse) {}
     }
+
+    Paths(acc).visit_item(item);
 }
 
 pub trait PrettyPrint {
@@ -84,28 +499,137 @@ where
 
 #[cfg(test)]
 mod tests {
-    use quote::ToTokens;
+    #[test]
+    fn cyclomatic_complexity() {
+        let item = syn::parse_str::<syn::ItemFn>(
+            "fn foo(x: Option<i32>) -> i32 {
+                if let Some(x) = x {
+                    for _ in 0..x {}
+                }
+                match x {
+                    Some(0) | None => 0,
+                    Some(x) if x > 0 && x < 10 => 1,
+                    Some(_) => {
+                        fn nested() { if true {} }
+                        2
+                    }
+                }
+            }",
+        )
+        .unwrap();
+
+        // 1 + `if` + `for` + 2 extra arms + a guard + `&&`.
+        assert_eq!(super::cyclomatic_complexity(&item.block), 7);
+        assert_eq!(super::fn_lines(&item.sig, &item.block), 13);
+    }
 
     #[test]
-    fn flatten_use_tree() {
-        let use_tree = syn::parse_str("use foo::{bar, baz::qux, jar::{a, b, c}};").unwrap();
-        let use_tree = match use_tree {
-            syn::Item::Use(syn::ItemUse { tree, .. }) => tree,
-            _ => unreachable!(),
+    fn cfg_predicates() {
+        let item =
+            |attrs| syn::parse_str::<syn::ItemFn>(&format!("{attrs} fn foo() {{}}")).unwrap();
+        let enabled = |attrs, cfg: &[&str]| {
+            let cfg = cfg.iter().map(|option| option.to_string()).collect::<Vec<_>>();
+            super::is_cfg_enabled(&item(attrs).attrs, &cfg)
         };
 
-        let paths = super::flatten_use_tree(&use_tree)
+        assert!(enabled("#[inline]", &[]));
+        assert!(enabled("#[cfg(unix)]", &["unix"]));
+        assert!(!enabled("#[cfg(unix)]", &["windows"]));
+        assert!(enabled(r#"#[cfg(feature = "serde")]"#, &[r#"feature="serde""#]));
+        assert!(!enabled(r#"#[cfg(feature = "serde")]"#, &["feature"]));
+        assert!(enabled("#[cfg(not(test))]", &[]));
+        assert!(enabled(r#"#[cfg(all(unix, any(test, feature = "a")))]"#, &["unix", "test"]));
+        assert!(!enabled("#[cfg(unix)] #[cfg(test)]", &["unix"]));
+    }
+
+    #[test]
+    fn compact_tokens() {
+        let ty = syn::parse_str::<syn::Type>("Vec<&'a mut dyn Fn(u32) -> u32>").unwrap();
+        assert_eq!(super::compact_tokens(ty), "Vec<&'a mut dyn Fn(u32)->u32>");
+    }
+
+    #[test]
+    fn typed_items() {
+        use crate::ir::{AssocType, GenericParamKind, Param, Visibility};
+
+        let item = syn::parse_str::<syn::ItemTrait>(
+            "trait Store<'a, T: Clone = String, const N: usize>: Send
+            where
+                T: 'a,
+            {
+                type Key: Hash;
+                const LIMIT: usize = N;
+                async fn get(&self, key: &'a Self::Key) -> Option<T>;
+            }",
+        )
+        .unwrap();
+
+        let generics = super::generics(&item.generics);
+        let params = generics.params.iter().map(|param| {
+            (param.name.as_str(), param.kind, param.bounds.join("+"), param.default.as_deref())
+        });
+        assert_eq!(
+            params.collect::<Vec<_>>(),
+            [
+                ("'a", GenericParamKind::Lifetime, String::new(), None),
+                ("T", GenericParamKind::Type, "Clone".to_owned(), Some("String")),
+                ("N", GenericParamKind::Const, String::new(), None),
+            ]
+        );
+        assert_eq!(generics.where_clause[0].bounded, "T");
+        assert_eq!(generics.where_clause[0].bounds, ["'a"]);
+
+        let items = super::trait_items(&item.items);
+        assert_eq!(
+            items.types,
+            [AssocType {
+                name: "Key".to_owned(),
+                vis: Visibility::Public,
+                generics: Default::default(),
+                bounds: vec!["Hash".to_owned()],
+                ty: None,
+            }]
+        );
+        assert!(items.consts[0].has_value);
+        let sig = &items.fns[0].sig;
+        assert!(sig.is_async && !items.fns[0].has_body);
+        assert_eq!(
+            sig.params,
+            [
+                Param { pat: "self".to_owned(), ty: "&Self".to_owned() },
+                Param { pat: "key".to_owned(), ty: "&'a Self::Key".to_owned() },
+            ]
+        );
+        assert_eq!(sig.output.as_deref(), Some("Option<T>"));
+
+        let item =
+            syn::parse_str::<syn::ItemEnum>("enum E { A = 1, B(pub u8), C { x: i32 } }").unwrap();
+        let variants = item.variants.iter().map(super::variant).collect::<Vec<_>>();
+        assert_eq!(variants[0].discriminant.as_deref(), Some("1"));
+        assert_eq!(
+            (variants[1].fields[0].name.as_deref(), variants[1].fields[0].vis),
+            (None, Visibility::Public)
+        );
+        assert_eq!(variants[2].fields[0].vis, Visibility::Public);
+        assert_eq!(variants[2].fields[0].name.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn flatten_use_tree() {
+        let item = syn::parse_str::<syn::ItemUse>("use foo::{bar, baz::{self, qux as q}, jar::*};")
+            .unwrap();
+
+        let paths = super::flatten_use_tree(&item.tree)
             .into_iter()
-            .map(|path| path.to_token_stream().to_string())
+            .map(|path| (path.segments.join("::"), path.name))
             .collect::<Vec<_>>();
         assert_eq!(
             paths,
             vec![
-                "foo :: bar",
-                "foo :: baz :: qux",
-                "foo :: jar :: a",
-                "foo :: jar :: b",
-                "foo :: jar :: c"
+                ("foo::bar".to_owned(), Some("bar".to_owned())),
+                ("foo::baz".to_owned(), Some("baz".to_owned())),
+                ("foo::baz::qux".to_owned(), Some("q".to_owned())),
+                ("foo::jar".to_owned(), None),
             ]
         );
     }
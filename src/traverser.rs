@@ -1,4 +1,10 @@
-use std::path::PathBuf;
+mod cache;
+mod scope;
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+};
 
 use anyhow::anyhow;
 use multipipe::Pipe;
@@ -6,70 +12,183 @@ use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use rayon::prelude::*;
 
+pub use self::cache::Cache;
+use self::{
+    cache::{CacheKey, FileModule, Submodule},
+    scope::Scope,
+};
 use crate::{
+    history,
     ir::{
-        Const, Enum, Fn, ItemCollection, Mod, Package, Static, Struct, Trait, TraitAlias, Type,
-        Union, Use,
+        Const, Enum, Fn, Impl, ItemCollection, Mod, Package, Static, Struct, Trait, TraitAlias,
+        Type, Union, Use, Visibility,
     },
     manifest::Manifest,
+    roots::{self, CrateRoot},
+    rust_project,
     syn_util::{self, PrettyPrint},
 };
 
+/// What to traverse and extract.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The project root directory, with the root `Cargo.toml`.
+    pub proj: String,
+    /// A file listing crate root files to traverse instead of the Cargo
+    /// workspace, or `-` for stdin. See [`roots`] for the format.
+    pub crate_roots: Option<String>,
+    /// A rust-analyzer `rust-project.json` file describing the crates to
+    /// traverse instead of the Cargo workspace.
+    pub rust_project: Option<String>,
+    /// Whether to extract dependencies between modules and items.
+    pub enable_edges: bool,
+    /// How far back to read the git history of modules, in any format
+    /// accepted by `git log --since`, if at all.
+    pub history: Option<String>,
+    /// The least visible items to extract, `Restricted` by default. Modules
+    /// are kept regardless if they declare file-backed submodules, and uses
+    /// for dependencies.
+    pub visibility: Visibility,
+    /// The set `cfg` options, e.g., `unix` or `feature = "serde"`, to drop the
+    /// items that `#[cfg]` attributes configure out. Without them, alternative
+    /// definitions of a module are merged, and only the first of an item is
+    /// kept, so that IDs stay unique.
+    pub cfg: Option<Vec<String>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            proj: Default::default(),
+            crate_roots: None,
+            rust_project: None,
+            enable_edges: false,
+            history: None,
+            visibility: Visibility::Restricted,
+            cfg: None,
+        }
+    }
+}
+
 // A context for traversing a module.
 #[derive(Clone)]
 struct Ctx<'a> {
-    // Command-line options.
-    args: &'a crate::cli::Args,
+    // Traversal options.
+    options: &'a Options,
+    // Extraction results of previously traversed files.
+    cache: &'a Cache,
     // The name of the containing package.
     package_name: String,
     // The name of the containing crate.
     crate_name: String,
+    // The ID of the module under consideration, e.g., `pkg::lib::foo`.
+    module_id: String,
     // The current working directory.
     dir: PathBuf,
     // The module name under consideration.
     module_name: String,
+    // The module file set by `#[path]`, overriding the lookup in `dir`.
+    path: Option<PathBuf>,
 }
 
 impl<'a> Ctx<'a> {
     fn new(
-        args: &'a crate::cli::Args,
+        options: &'a Options,
+        cache: &'a Cache,
         dir: impl Into<PathBuf>,
         module_name: impl Into<String>,
         package_name: impl Into<String>,
         crate_name: impl Into<String>,
     ) -> Self {
+        let (package_name, crate_name) = (package_name.into(), crate_name.into());
         Self {
-            args,
+            options,
+            cache,
             dir: dir.into(),
             module_name: module_name.into(),
-            package_name: package_name.into(),
-            crate_name: crate_name.into(),
+            module_id: format!("{package_name}::{crate_name}"),
+            package_name,
+            crate_name,
+            path: None,
         }
     }
 }
 
-pub fn traverse(
-    args: &crate::cli::Args,
-    manifest: &Manifest,
-) -> anyhow::Result<impl Iterator<Item = Package>> {
-    manifest
-        .members(&args.proj)?
+/// Traverses the workspace members of the project or, with
+/// [`Options::crate_roots`] or [`Options::rust_project`], the listed crates.
+pub fn traverse(options: &Options, cache: &Cache) -> anyhow::Result<impl Iterator<Item = Package>> {
+    let packages = match (&options.rust_project, &options.crate_roots) {
+        (Some(path), _) => {
+            let project = rust_project::load(Path::new(path))?;
+            let mut packages = traverse_roots(&project.roots, options, cache)?;
+            project.resolve_deps(&mut packages);
+            packages
+        }
+        (None, Some(list)) => {
+            traverse_roots(&roots::load(list, Path::new(&options.proj))?, options, cache)?
+        }
+        (None, None) => Manifest::parse(&options.proj)?
+            .members(&options.proj)?
+            .par_iter()
+            .filter_map(|member| match traverse_member(member, options, cache) {
+                Ok(package) => Some(package),
+                Err(e) => {
+                    let member_display = member.display();
+                    log::debug!("Failed to traverse member {member_display}: {e}. Skipping.");
+                    None
+                }
+            })
+            .collect::<Vec<_>>(),
+    };
+    packages.pipe(|mut packages| {
+        if let Some(since) = &options.history {
+            history::overlay(Path::new(&options.proj), since, &mut packages)?;
+        }
+        Ok(packages.into_iter())
+    })
+}
+
+/// Returns the directories of the packages that [`traverse`] visits.
+pub fn members(options: &Options) -> anyhow::Result<Vec<PathBuf>> {
+    match (&options.rust_project, &options.crate_roots) {
+        (Some(path), _) => Ok(roots::members(&rust_project::load(Path::new(path))?.roots)),
+        (None, Some(list)) => Ok(roots::members(&roots::load(list, Path::new(&options.proj))?)),
+        (None, None) => Manifest::parse(&options.proj)?.members(&options.proj),
+    }
+}
+
+// Traverses crates from an explicit list, grouped into packages in the order
+// of the list.
+fn traverse_roots(
+    roots: &[CrateRoot],
+    options: &Options,
+    cache: &Cache,
+) -> anyhow::Result<Vec<Package>> {
+    let crates = roots
         .par_iter()
-        .filter_map(|member| match traverse_member(member, args) {
-            Ok(package) => Some(package),
-            Err(e) => {
-                let member_display = member.display();
-                log::debug!("Failed to traverse member {member_display}: {e}. Skipping.");
-                None
-            }
+        .map(|root| {
+            let dir = root.path.parent().map(ToOwned::to_owned).unwrap_or_default();
+            let ctx = Ctx {
+                path: Some(root.path.clone()),
+                ..Ctx::new(options, cache, dir, &root.name, &root.package, &root.name)
+            };
+            traverse_mod(&ctx)?
+                .ok_or_else(|| anyhow!("Cannot find crate root {}", root.path.display()))
         })
-        .collect::<Vec<_>>()
-        .into_iter()
-        .pipe(Ok)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut packages = Vec::<Package>::new();
+    for (root, crate_) in roots.iter().zip(crates) {
+        match packages.iter_mut().find(|package| package.name == root.package) {
+            Some(package) => package.crates.push(crate_),
+            None => packages.push(Package { name: root.package.clone(), crates: vec![crate_] }),
+        }
+    }
+    Ok(packages)
 }
 
 // Traverses a workspace member.
-fn traverse_member(member: &PathBuf, args: &crate::cli::Args) -> anyhow::Result<Package> {
+fn traverse_member(member: &PathBuf, options: &Options, cache: &Cache) -> anyhow::Result<Package> {
     let member_display = member.display();
     log::trace!("Traversing member {member_display}.");
 
@@ -84,7 +203,8 @@ fn traverse_member(member: &PathBuf, args: &crate::cli::Args) -> anyhow::Result<
     let crates = manifest
         .read_package_targets(&member)?
         .map(|target| {
-            let ctx = Ctx::new(args, &target.path, &target.name, &package_name, &target.name);
+            let ctx =
+                Ctx::new(options, cache, &target.path, &target.name, &package_name, &target.name);
             traverse_mod(&ctx)?
                 .ok_or_else(|| anyhow!("Failed to traverse workspace member {member_display}."))
         })
@@ -94,9 +214,9 @@ fn traverse_member(member: &PathBuf, args: &crate::cli::Args) -> anyhow::Result<
 }
 
 fn traverse_mod(ctx: &Ctx) -> anyhow::Result<Option<Mod>> {
-    let Ctx { args, dir, module_name, package_name, crate_name } = ctx;
-    let (mut file, module_path) = match open_file(ctx) {
-        Ok((file, module_path)) => (file, module_path),
+    let Ctx { options, cache, dir, module_name, module_id, path, .. } = ctx;
+    let module_path = match find_file(ctx) {
+        Ok(module_path) => module_path,
         Err(e) => {
             log::debug!(
                 "Cannot find module {module_name} in {module_dir}: {e}. Skipping.",
@@ -105,65 +225,234 @@ fn traverse_mod(ctx: &Ctx) -> anyhow::Result<Option<Mod>> {
             return Ok(None);
         }
     };
-    let parse_tree = read_parse_tree(&mut file)?;
-    drop(file);
 
     log::trace!("Traversing module {}.", module_path.display());
 
-    let dir = if module_name != "main" && module_name != "lib" {
+    let file_dir = module_path.parent().map(ToOwned::to_owned).unwrap_or_default();
+    // Files included with `#[path]` are treated like `mod.rs` files.
+    let dir = if path.is_some() {
+        file_dir.clone()
+    } else if module_name != "main" && module_name != "lib" {
         [dir, &PathBuf::from(module_name)].iter().collect()
     } else {
         dir.clone()
     };
-    let ctx = Ctx::new(args, dir, module_name, package_name, crate_name);
+    let ctx = Ctx { dir, path: None, ..ctx.clone() };
+
+    let key = CacheKey { path: canonicalize(&module_path), module_id: module_id.clone() };
+    let file = key.path.clone();
+    let FileModule { mut module, submodules } = cache.get_or_extract(key, options, |source| {
+        let file_module = extract_file(&ctx, source);
+        // Line numbers are only needed while extracting. Forgetting them
+        // keeps the source map behind them from growing with every parse,
+        // e.g., with `--watch`.
+        proc_macro2::extra::invalidate_current_thread_spans();
+        file_module
+    })?;
+    // Entries from the persistent cache may come from a file with another name.
+    module.name = module_name.clone();
+    module.file = Some(file);
+
+    // Removing a missing submodule does not shift the indices of the preceding
+    // ones.
+    for Submodule { index_path, path } in submodules.iter().rev() {
+        let (index, parents) = index_path.split_last().expect("Must be a non-empty index path");
+        // Submodules declared in inline modules live in their directories.
+        let mut dir = ctx.dir.clone();
+        let mut module_id = ctx.module_id.clone();
+        let mut mods = &mut module.items.mods;
+        for &i in parents {
+            dir.push(&mods[i].name);
+            module_id = format!("{module_id}::{}", mods[i].name);
+            mods = &mut mods[i].items.mods;
+        }
+        // Outside inline modules, `#[path]` is relative to the current file.
+        let path = path.as_ref().map(|path| match parents {
+            [] => file_dir.join(path),
+            _ => dir.join(path),
+        });
+        let module_name = mods[*index].name.clone();
+        let module_id = format!("{module_id}::{module_name}");
+        match traverse_mod(&Ctx { module_name, module_id, dir, path, ..ctx.clone() })? {
+            Some(submodule) => mods[*index] = Mod { vis: mods[*index].vis, ..submodule },
+            None => {
+                mods.remove(*index);
+            }
+        }
+    }
+    merge_alternatives(&mut module.items.mods);
 
-    let mut module = Mod::new(module_name);
-    traverse_item_vec(&ctx, &mut module.items, &mut module.deps, parse_tree.items)?;
     Ok(Some(module))
 }
 
-fn open_file(Ctx { dir, module_name, .. }: &Ctx) -> anyhow::Result<(std::fs::File, PathBuf)> {
-    let new_style_path = [dir, &format!("{module_name}.rs").into()].iter().collect();
-    let old_style_path = [dir, &format!("{module_name}/mod.rs").into()].iter().collect();
+fn extract_file(ctx: &Ctx, source: &str) -> anyhow::Result<FileModule> {
+    let parse_tree = syn::parse_file(source)?;
+    let mut file_module = FileModule { module: Mod::new(&ctx.module_name), submodules: vec![] };
+    let FileModule { module, submodules } = &mut file_module;
+    module.metrics.lines = source.lines().count();
+    traverse_item_vec(
+        ctx,
+        module,
+        &mut Submodules { prefix: vec![], acc: submodules },
+        parse_tree.items,
+    )?;
+    Ok(file_module)
+}
 
-    let (file, module_path) = match std::fs::File::open(&new_style_path) {
-        Ok(file) => (file, new_style_path),
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            std::fs::File::open(&old_style_path).map(|file| (file, old_style_path))?
-        }
-        Err(e) => return Err(e.into()),
-    };
+fn find_file(Ctx { dir, module_name, path, .. }: &Ctx) -> anyhow::Result<PathBuf> {
+    if let Some(path) = path {
+        return match path.is_file() {
+            true => Ok(path.clone()),
+            false => Err(anyhow!("{} does not exist", path.display())),
+        };
+    }
+
+    let new_style_path: PathBuf = [dir, &format!("{module_name}.rs").into()].iter().collect();
+    let old_style_path: PathBuf = [dir, &format!("{module_name}/mod.rs").into()].iter().collect();
 
-    Ok((file, module_path))
+    if new_style_path.is_file() {
+        Ok(new_style_path)
+    } else if old_style_path.is_file() {
+        Ok(old_style_path)
+    } else {
+        Err(anyhow!("Neither {} nor {} exists", new_style_path.display(), old_style_path.display()))
+    }
 }
 
-fn read_parse_tree(file: &mut std::fs::File) -> anyhow::Result<syn::File> {
-    std::io::read_to_string(file)?.pipe_ref(syn::parse_file)?.pipe(Ok)
+// Makes paths from the traverser and the watcher comparable.
+fn canonicalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+// File-backed submodules found while traversing a file.
+struct Submodules<'a> {
+    // The index path of the enclosing inline module.
+    prefix: Vec<usize>,
+    acc: &'a mut Vec<Submodule>,
 }
 
 fn traverse_item_vec(
     ctx: &Ctx,
-    acc: &mut ItemCollection,
-    deps: &mut Vec<String>,
+    module: &mut Mod,
+    submodules: &mut Submodules,
     items: Vec<syn::Item>,
 ) -> anyhow::Result<()> {
+    // Items configured out do not exist at all.
+    let items = match &ctx.options.cfg {
+        Some(cfg) => items
+            .into_iter()
+            .filter(|item| syn_util::is_cfg_enabled(syn_util::item_attrs(item), cfg))
+            .collect(),
+        None => items,
+    };
+    let crate_id = format!("{}::{}", ctx.package_name, ctx.crate_name);
+    let scope = Scope::new(&crate_id, &ctx.module_id, &items);
+    let mut paths = BTreeSet::new();
     for item in items {
-        traverse_item(ctx, acc, deps, item)?;
+        syn_util::measure_item(&item, &mut module.metrics);
+        // Like their items, the lines of inline modules are their own.
+        if let syn::Item::Mod(syn::ItemMod { mod_token, content: Some((brace, _)), .. }) = &item {
+            let lines = syn_util::lines_between(mod_token.span, brace.span.close());
+            module.metrics.lines = module.metrics.lines.saturating_sub(lines);
+        }
+        if ctx.options.enable_edges {
+            syn_util::collect_paths(&item, &mut paths);
+        }
+        traverse_item(ctx, &scope, &mut module.items, &mut module.deps, submodules, item)?;
     }
+    let paths =
+        paths.iter().filter_map(|path| scope.resolve_code_path(path)).collect::<BTreeSet<_>>();
+    module.paths.extend(paths);
+    dedup_items(&mut module.items);
     Ok(())
 }
 
+// Drops all but the first of alternative definitions of an item under
+// different `#[cfg]`s, which share its ID. Their metrics and dependencies are
+// kept. Modules are merged by `merge_alternatives` once their files are
+// traversed.
+fn dedup_items(items: &mut ItemCollection) {
+    macro_rules! dedup {
+        ($($items:ident),*) => {
+            $(
+                let mut names = HashSet::new();
+                items.$items.retain(|item| names.insert(item.name.clone()));
+            )*
+        };
+    }
+
+    dedup!(consts, enums, fns, statics, structs, traits, trait_aliases, types, unions);
+}
+
+// Merges alternative definitions of modules under different `#[cfg]`s, which
+// share their IDs, into the first ones, down through inline modules.
+fn merge_alternatives(mods: &mut Vec<Mod>) {
+    for module in std::mem::take(mods) {
+        match mods.iter_mut().find(|first| first.name == module.name) {
+            Some(first) => {
+                log::debug!("Merging an alternative definition of module {}.", module.name);
+                merge_mod(first, module);
+                merge_alternatives(&mut first.items.mods);
+            }
+            None => mods.push(module),
+        }
+    }
+    for module in mods.iter_mut().filter(|module| module.file.is_none()) {
+        merge_alternatives(&mut module.items.mods);
+    }
+}
+
+fn merge_mod(acc: &mut Mod, module: Mod) {
+    let Mod { vis, items, deps, paths, metrics, file, alternative_files, .. } = module;
+    macro_rules! append {
+        ($($items:ident),*) => {
+            $(acc.items.$items.extend(items.$items);)*
+        };
+    }
+
+    append!(
+        consts,
+        enums,
+        fns,
+        impls,
+        mods,
+        statics,
+        structs,
+        traits,
+        trait_aliases,
+        types,
+        unions,
+        uses
+    );
+    dedup_items(&mut acc.items);
+    acc.vis = acc.vis.min(vis);
+    acc.deps.extend(deps);
+    acc.paths.extend(paths);
+    acc.paths.sort();
+    acc.paths.dedup();
+    acc.metrics.lines += metrics.lines;
+    acc.metrics.items += metrics.items;
+    acc.metrics.fns += metrics.fns;
+    acc.metrics.fn_lines += metrics.fn_lines;
+    acc.metrics.complexity += metrics.complexity;
+    acc.alternative_files.extend(file.into_iter().chain(alternative_files));
+}
+
 fn traverse_item(
     ctx: &Ctx,
+    scope: &Scope,
     acc: &mut ItemCollection,
     deps: &mut Vec<String>,
+    submodules: &mut Submodules,
     item: syn::Item,
 ) -> anyhow::Result<()> {
-    // Skip private items, except module declarations.
-    if !syn_util::is_public_item(&item)
+    // Skip items less visible than requested, except module declarations.
+    if !syn_util::item_visibility(&item).is_at_least(ctx.options.visibility)
         && !matches!(item, syn::Item::Mod(_))
         // Used in computing dependencies.
         && !matches!(item, syn::Item::Use(_))
+        // Implementations have no visibility of their own.
+        && !matches!(item, syn::Item::Impl(_))
     {
         return Ok(());
     }
@@ -171,57 +460,138 @@ fn traverse_item(
     match item {
         syn::Item::Const(item) => {
             let item = syn::ItemConst { attrs: vec![], ..item };
-            acc.consts.push(Const { name: item.ident.to_string(), repr: item.pretty_print() });
+            acc.consts.push(Const {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                ty: syn_util::compact_tokens(&item.ty),
+                repr: item.pretty_print(),
+            });
         }
         syn::Item::Enum(item) => {
-            let item = syn::ItemEnum { attrs: vec![], ..item };
-            acc.enums.push(Enum { name: item.ident.to_string(), repr: item.pretty_print() })
+            acc.impls.extend(derived_impls(&item.attrs, &item.ident));
+            let item = syn::ItemEnum { attrs: syn_util::api_attrs(item.attrs), ..item };
+            acc.enums.push(Enum {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                generics: syn_util::generics(&item.generics),
+                variants: item.variants.iter().map(syn_util::variant).collect(),
+                repr: item.pretty_print(),
+            })
         }
         syn::Item::Fn(item) => {
             let item = syn::ItemFn { attrs: vec![], ..item };
             let curly_braces = quote! {{}};
             let proper_syntax =
                 TokenStream::from_iter([item.sig.to_token_stream(), curly_braces].into_iter());
-            acc.fns
-                .push(Fn { name: item.sig.ident.to_string(), repr: proper_syntax.pretty_print() });
+            acc.fns.push(Fn {
+                name: item.sig.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                repr: proper_syntax.pretty_print(),
+                sig: syn_util::signature(&item.sig),
+                lines: syn_util::fn_lines(&item.sig, &item.block),
+                complexity: syn_util::cyclomatic_complexity(&item.block),
+            });
+        }
+        syn::Item::Impl(item) => {
+            let items =
+                syn_util::impl_items(&item.items, item.trait_.is_some(), ctx.options.visibility);
+            let item = syn::ItemImpl { attrs: vec![], items: vec![], ..item };
+            acc.impls.push(Impl {
+                trait_: item.trait_.as_ref().map(|(bang, path, _)| {
+                    format!(
+                        "{}{}",
+                        if bang.is_some() { "!" } else { "" },
+                        syn_util::compact_tokens(path)
+                    )
+                }),
+                self_ty: syn_util::compact_tokens(&item.self_ty),
+                derived: false,
+                generics: syn_util::generics(&item.generics),
+                items,
+                repr: item.pretty_print(),
+            });
         }
         syn::Item::Mod(item) => {
-            let item = syn::ItemMod { attrs: vec![], ..item };
-            if let Some(new_module) = traverse_item_mod(ctx, item)? {
+            let index = acc.mods.len();
+            submodules.prefix.push(index);
+            let new_module = traverse_item_mod(ctx, submodules, item);
+            submodules.prefix.pop();
+            if let Some(new_module) = new_module? {
                 acc.mods.push(new_module);
             }
         }
         syn::Item::Static(item) => {
             let item = syn::ItemStatic { attrs: vec![], ..item };
-            acc.statics.push(Static { name: item.ident.to_string(), repr: item.pretty_print() })
+            acc.statics.push(Static {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                ty: syn_util::compact_tokens(&item.ty),
+                mutable: matches!(item.mutability, syn::StaticMutability::Mut(_)),
+                repr: item.pretty_print(),
+            })
         }
         syn::Item::Struct(item) => {
-            let item = syn::ItemStruct { attrs: vec![], ..item };
-            acc.structs.push(Struct { name: item.ident.to_string(), repr: item.pretty_print() })
+            acc.impls.extend(derived_impls(&item.attrs, &item.ident));
+            let item = syn::ItemStruct { attrs: syn_util::api_attrs(item.attrs), ..item };
+            acc.structs.push(Struct {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                generics: syn_util::generics(&item.generics),
+                fields: item.fields.iter().map(syn_util::field).collect(),
+                repr: item.pretty_print(),
+            })
         }
         syn::Item::Trait(item) => {
             let item = syn::ItemTrait { attrs: vec![], ..item };
-            acc.traits.push(Trait { name: item.ident.to_string(), repr: item.pretty_print() });
+            acc.traits.push(Trait {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                generics: syn_util::generics(&item.generics),
+                supertraits: item.supertraits.iter().map(syn_util::compact_tokens).collect(),
+                items: syn_util::trait_items(&item.items),
+                repr: item.pretty_print(),
+            });
         }
         syn::Item::TraitAlias(item) => {
             let item = syn::ItemTraitAlias { attrs: vec![], ..item };
-            acc.trait_aliases
-                .push(TraitAlias { name: item.ident.to_string(), repr: item.pretty_print() })
+            acc.trait_aliases.push(TraitAlias {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                generics: syn_util::generics(&item.generics),
+                bounds: item.bounds.iter().map(syn_util::compact_tokens).collect(),
+                repr: item.pretty_print(),
+            })
         }
         syn::Item::Type(item) => {
             let item = syn::ItemType { attrs: vec![], ..item };
-            acc.types.push(Type { name: item.ident.to_string(), repr: item.pretty_print() });
+            acc.types.push(Type {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                generics: syn_util::generics(&item.generics),
+                ty: syn_util::compact_tokens(&item.ty),
+                repr: item.pretty_print(),
+            });
         }
         syn::Item::Union(item) => {
+            acc.impls.extend(derived_impls(&item.attrs, &item.ident));
             let item = syn::ItemUnion { attrs: vec![], ..item };
-            acc.unions.push(Union { name: item.ident.to_string(), repr: item.pretty_print() });
+            acc.unions.push(Union {
+                name: item.ident.to_string(),
+                vis: syn_util::visibility(&item.vis),
+                generics: syn_util::generics(&item.generics),
+                fields: item.fields.named.iter().map(syn_util::field).collect(),
+                repr: item.pretty_print(),
+            });
         }
         syn::Item::Use(item) => {
             let item = syn::ItemUse { attrs: vec![], ..item };
-            if ctx.args.enable_edges {
-                deps.append(&mut traverse_item_use(ctx, &item)?)
-            };
-            acc.uses.push(Use { repr: item.pretty_print() });
+            let use_deps = if ctx.options.enable_edges { scope.resolve_use(&item) } else { vec![] };
+            deps.extend(use_deps.iter().cloned());
+            acc.uses.push(Use {
+                vis: syn_util::visibility(&item.vis),
+                repr: item.pretty_print(),
+                deps: use_deps,
+            });
         }
         _ => return Ok(()),
     };
@@ -229,48 +599,156 @@ fn traverse_item(
     Ok(())
 }
 
-fn traverse_item_mod(ctx: &Ctx, item: syn::ItemMod) -> anyhow::Result<Option<Mod>> {
+// Trait implementations generated by `#[derive]` on a type.
+fn derived_impls(attrs: &[syn::Attribute], ident: &syn::Ident) -> impl Iterator<Item = Impl> {
+    let self_ty = ident.to_string();
+    syn_util::derived_traits(attrs).into_iter().map(move |trait_| Impl {
+        repr: format!("#[derive({trait_})]"),
+        trait_: Some(trait_),
+        self_ty: self_ty.clone(),
+        derived: true,
+        ..Default::default()
+    })
+}
+
+fn traverse_item_mod(
+    ctx: &Ctx,
+    submodules: &mut Submodules,
+    item: syn::ItemMod,
+) -> anyhow::Result<Option<Mod>> {
+    let is_visible = syn_util::visibility(&item.vis).is_at_least(ctx.options.visibility);
     match item.content {
-        // A public module definition: `pub mod foo { ... }`.
-        Some((_brace, items)) if syn_util::is_public_item(&item.clone().into()) => {
+        // A module definition: `mod foo { ... }`. Less visible ones are kept
+        // only for the file-backed submodules they declare.
+        Some((brace, items)) => {
+            let declared = submodules.acc.len();
             let mut new_module = Mod::new(item.ident.to_string());
-            traverse_item_vec(ctx, &mut new_module.items, &mut new_module.deps, items)?;
-            Ok(Some(new_module))
+            new_module.vis = syn_util::visibility(&item.vis);
+            new_module.metrics.lines =
+                syn_util::lines_between(item.mod_token.span, brace.span.close());
+            let module_id = format!("{}::{}", ctx.module_id, item.ident);
+            traverse_item_vec(
+                &Ctx { module_id, ..ctx.clone() },
+                &mut new_module,
+                submodules,
+                items,
+            )?;
+            Ok((is_visible || submodules.acc.len() > declared).then_some(new_module))
         }
-        // A private module definition: `mod foo { ... }`.
-        Some(_) => Ok(None),
-        // A module declaration: `mod foo;`.
+        // A module declaration: `mod foo;`. It is resolved by `traverse_mod`
+        // after the whole file is traversed.
         None => {
-            let module_name = item.ident.to_string();
-            Ok(traverse_mod(&Ctx { module_name, ..ctx.clone() })?)
+            submodules.acc.push(Submodule {
+                index_path: submodules.prefix.clone(),
+                path: syn_util::path_attr(&item.attrs),
+            });
+            let mut placeholder = Mod::new(item.ident.to_string());
+            placeholder.vis = syn_util::visibility(&item.vis);
+            Ok(Some(placeholder))
         }
     }
 }
 
-fn traverse_item_use(ctx: &Ctx, item: &syn::ItemUse) -> anyhow::Result<Vec<String>> {
-    syn_util::flatten_use_tree(&item.tree)
-        .into_iter()
-        .filter_map(|path| {
-            let mut segments = path.segments.into_iter().collect::<Vec<_>>();
-            if let Some(first_segment) = segments.first() {
-                // The current crate item.
-                if first_segment.ident == "crate" {
-                    segments.remove(0);
-                    return Some(format!(
-                        "{}::{}::{}",
-                        ctx.package_name,
-                        ctx.crate_name,
-                        syn_util::format_path(segments),
-                    ));
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                // Perhaps some other workspace member.
-                segments.insert(1, syn_util::path_segment("lib"));
-                return Some(syn_util::format_path(segments));
-            }
+    #[test]
+    fn inline_module_metrics() {
+        let proj = std::env::temp_dir().join(format!("traverser-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            (
+                "src/lib.rs",
+                "pub fn run() {}\n\npub mod db {\n    pub fn connect() {}\n}\n\nmod tests {\n    fn \
+                 check() {}\n}\n",
+            ),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
 
-            None
-        })
-        .collect::<Vec<_>>()
-        .pipe(Ok)
+        let options = Options { proj: proj.to_str().unwrap().to_owned(), ..Default::default() };
+        let packages = traverse(&options, &Cache::default()).map(Iterator::collect::<Vec<_>>);
+        std::fs::remove_dir_all(&proj).unwrap();
+        let lib = &packages.unwrap()[0].crates[0];
+        let db = &lib.items.mods[0];
+        assert_eq!((lib.metrics.lines, lib.metrics.fns), (3, 1));
+        assert_eq!((db.metrics.lines, db.metrics.fns), (3, 1));
+    }
+
+    #[test]
+    fn visibility_and_cfg() {
+        let proj = std::env::temp_dir().join(format!("traverser-cfg-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            (
+                "src/lib.rs",
+                "pub fn a() {}\npub(crate) fn b() {}\nfn c() {}\n#[cfg(unix)]\npub fn d() {}\n\
+                 #[cfg(windows)]\npub fn d() {}\n#[cfg(feature = \"serde\")]\npub fn e() {}\n#[cfg(test)]\nmod tests {}\n",
+            ),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let proj_str = proj.to_str().unwrap().to_owned();
+        let fns = |options: Options| {
+            let packages =
+                traverse(&Options { proj: proj_str.clone(), ..options }, &Cache::default())
+                    .unwrap()
+                    .collect::<Vec<_>>();
+            let lib = &packages[0].crates[0];
+            lib.items.fns.iter().map(|item| item.name.clone()).collect::<Vec<_>>()
+        };
+        let default = fns(Options::default());
+        let public = fns(Options { visibility: Visibility::Public, ..Default::default() });
+        let private = fns(Options { visibility: Visibility::Private, ..Default::default() });
+        let configured = fns(Options {
+            cfg: Some(vec!["unix".to_owned(), "feature=\"serde\"".to_owned()]),
+            ..Default::default()
+        });
+        let unconfigured = fns(Options { cfg: Some(vec![]), ..Default::default() });
+        std::fs::remove_dir_all(&proj).unwrap();
+
+        assert_eq!(default, ["a", "b", "d", "e"]);
+        assert_eq!(public, ["a", "d", "e"]);
+        assert_eq!(private, ["a", "b", "c", "d", "e"]);
+        assert_eq!(configured, ["a", "b", "d", "e"]);
+        assert_eq!(unconfigured, ["a", "b"]);
+    }
+
+    #[test]
+    fn cfg_alternative_modules() {
+        let proj = std::env::temp_dir().join(format!("traverser-alt-{}", std::process::id()));
+        for (file, contents) in [
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            (
+                "src/lib.rs",
+                "#[cfg(unix)]\n#[path = \"unix.rs\"]\nmod imp;\n#[cfg(windows)]\n#[path = \
+                 \"windows.rs\"]\npub mod imp;\n",
+            ),
+            ("src/unix.rs", "pub fn spawn() {}\npub fn fork() {}\n"),
+            ("src/windows.rs", "pub fn spawn() {}\npub fn create_process() {}\n"),
+        ] {
+            let path = proj.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+
+        let options = Options { proj: proj.to_str().unwrap().to_owned(), ..Default::default() };
+        let packages = traverse(&options, &Cache::default()).map(Iterator::collect::<Vec<_>>);
+        let windows = canonicalize(&proj.join("src/windows.rs"));
+        std::fs::remove_dir_all(&proj).unwrap();
+        let lib = &packages.unwrap()[0].crates[0];
+        assert_eq!(lib.items.mods.len(), 1);
+        let imp = &lib.items.mods[0];
+        let fns = imp.items.fns.iter().map(|item| item.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(fns, ["spawn", "fork", "create_process"]);
+        assert_eq!(imp.vis, Visibility::Public);
+        assert_eq!((imp.metrics.lines, imp.metrics.fns), (4, 4));
+        assert_eq!(imp.alternative_files, [windows]);
+    }
 }
@@ -0,0 +1,189 @@
+//! Caching extraction results of individual source files.
+//!
+//! Within a process, entries are keyed by file path and live until the file
+//! changes. With a cache directory, entries are also persisted across runs,
+//! keyed by a SHA-256 hash of the file contents, the traversal options, the
+//! version of the tool, and [`CACHE_FORMAT_VERSION`], so that unchanged files
+//! are neither parsed nor pretty-printed again.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{canonicalize, Options};
+use crate::ir::Mod;
+
+/// The version of the layout of persisted entries. Bump it whenever the
+/// serialized IR changes, e.g., with a new field. Other changes of extraction
+/// are covered by the tool version, which is also in the key.
+pub const CACHE_FORMAT_VERSION: u32 = 6;
+
+/// Extraction results of individual source files, reused across traversals.
+///
+/// File-backed submodules (`mod foo;`) are not part of a cached entry, so that
+/// a change in one file invalidates only that file.
+#[derive(Default)]
+pub struct Cache {
+    files: Mutex<HashMap<CacheKey, FileModule>>,
+    // The persistent cache directory, if any.
+    dir: Option<PathBuf>,
+}
+
+// The same file may be included into several crates or modules, with
+// different dependency paths.
+#[derive(Clone, Eq, PartialEq, Hash)]
+pub struct CacheKey {
+    pub path: PathBuf,
+    pub module_id: String,
+}
+
+// A module extracted from a single file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FileModule {
+    // The module, with empty placeholders for file-backed submodules.
+    pub module: Mod,
+    // The placeholders, in the order of appearance.
+    pub submodules: Vec<Submodule>,
+}
+
+// A placeholder for a file-backed submodule.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Submodule {
+    // The index path of the placeholder through nested `items.mods`.
+    pub index_path: Vec<usize>,
+    // The value of the `#[path]` attribute, if any.
+    pub path: Option<PathBuf>,
+}
+
+impl Cache {
+    /// Creates a cache, persisted in `dir` if provided.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { files: Default::default(), dir }
+    }
+
+    /// Forgets the given files, so that they are parsed again on the next
+    /// traversal.
+    pub fn invalidate(&self, paths: &[PathBuf]) {
+        let paths = paths.iter().map(|path| canonicalize(path)).collect::<Vec<_>>();
+        self.files.lock().expect("Poisoned cache").retain(|key, _| !paths.contains(&key.path));
+    }
+
+    /// Returns the cached module for `key`, or reads the file and extracts the
+    /// module from its source with `extract`.
+    pub fn get_or_extract(
+        &self,
+        key: CacheKey,
+        options: &Options,
+        extract: impl FnOnce(&str) -> anyhow::Result<FileModule>,
+    ) -> anyhow::Result<FileModule> {
+        if let Some(file_module) = self.files.lock().expect("Poisoned cache").get(&key) {
+            return Ok(file_module.clone());
+        }
+
+        let source = std::fs::read_to_string(&key.path)?;
+        let entry_path = self.dir.as_ref().map(|dir| dir.join(entry_name(&key, options, &source)));
+
+        let file_module = match entry_path.as_ref().and_then(|path| read_entry(path)) {
+            Some(file_module) => file_module,
+            None => {
+                let file_module = extract(&source)?;
+                if let Some(path) = &entry_path {
+                    if let Err(e) = write_entry(path, &file_module) {
+                        log::debug!("Failed to write cache entry {}: {e}.", path.display());
+                    }
+                }
+                file_module
+            }
+        };
+
+        self.files.lock().expect("Poisoned cache").insert(key, file_module.clone());
+        Ok(file_module)
+    }
+}
+
+// The file name of the persisted entry for `source`.
+fn entry_name(key: &CacheKey, options: &Options, source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(CACHE_FORMAT_VERSION.to_le_bytes());
+    hasher.update((env!("CARGO_PKG_VERSION").len() as u64).to_le_bytes());
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    hasher.update([
+        u8::from(options.enable_edges),
+        options.visibility as u8,
+        u8::from(options.cfg.is_some()),
+    ]);
+    // The lengths keep the `cfg` options, the ID, and the source apart.
+    if let Some(cfg) = &options.cfg {
+        hasher.update((cfg.len() as u64).to_le_bytes());
+        for option in cfg {
+            hasher.update((option.len() as u64).to_le_bytes());
+            hasher.update(option);
+        }
+    }
+    hasher.update((key.module_id.len() as u64).to_le_bytes());
+    hasher.update(&key.module_id);
+    hasher.update(source);
+    format!("{:x}.json", hasher.finalize())
+}
+
+fn read_entry(path: &Path) -> Option<FileModule> {
+    let contents = std::fs::read(path).ok()?;
+    match serde_json::from_slice(&contents) {
+        Ok(file_module) => Some(file_module),
+        Err(e) => {
+            log::debug!("Corrupted cache entry {}: {e}. Ignoring.", path.display());
+            None
+        }
+    }
+}
+
+fn write_entry(path: &Path, file_module: &FileModule) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // Concurrent writers of the same entry must not observe a partial file.
+    static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let tmp_suffix = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let tmp_path = path.with_extension(format!("{}-{tmp_suffix}.tmp", std::process::id()));
+    std::fs::write(&tmp_path, serde_json::to_vec(file_module)?)?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Visibility;
+
+    #[test]
+    fn entry_names() {
+        let key = CacheKey { path: PathBuf::from("src/lib.rs"), module_id: "app::lib".to_owned() };
+        let options = Options::default();
+        let name = entry_name(&key, &options, "fn foo() {}");
+        assert_eq!(name.len(), 64 + ".json".len());
+        assert_eq!(
+            name,
+            entry_name(&CacheKey { path: PathBuf::new(), ..key.clone() }, &options, "fn foo() {}")
+        );
+
+        let other_key = CacheKey { module_id: "app::lib::db".to_owned(), ..key.clone() };
+        assert_ne!(name, entry_name(&other_key, &options, "fn foo() {}"));
+        assert_ne!(name, entry_name(&key, &options, "fn bar() {}"));
+        for other_options in [
+            Options { enable_edges: true, ..Default::default() },
+            Options { visibility: Visibility::Private, ..Default::default() },
+            Options { cfg: Some(vec![]), ..Default::default() },
+            Options { cfg: Some(vec!["unix".to_owned()]), ..Default::default() },
+        ] {
+            assert_ne!(name, entry_name(&key, &other_options, "fn foo() {}"));
+        }
+    }
+}
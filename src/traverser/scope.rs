@@ -0,0 +1,140 @@
+//! Resolution of paths in a module into vertex IDs.
+//!
+//! Paths starting with `crate`, `self`, and `super` are resolved against the
+//! module. Other paths start either with a name in the module's scope, i.e.,
+//! an item it declares or a name it imports, or with the name of another
+//! crate, e.g., `serde::Serialize` (see [`id::LIB`]). Names brought into scope
+//! by glob imports are not tracked.
+
+use std::collections::HashMap;
+
+use crate::{ir::id, syn_util};
+
+/// The names in scope of a module.
+pub struct Scope {
+    crate_id: String,
+    module_id: String,
+    // Names declared or imported by the module, mapped to their paths.
+    names: HashMap<String, String>,
+}
+
+impl Scope {
+    /// Creates the scope of the module `module_id` in crate `crate_id`, with
+    /// the given items.
+    pub fn new(crate_id: &str, module_id: &str, items: &[syn::Item]) -> Self {
+        let mut scope = Self {
+            crate_id: crate_id.to_owned(),
+            module_id: module_id.to_owned(),
+            names: HashMap::new(),
+        };
+        for ident in items.iter().filter_map(syn_util::item_ident) {
+            scope.names.insert(ident.to_string(), format!("{module_id}::{ident}"));
+        }
+
+        // Imports may start with declared names, but not with other imports.
+        let mut imports = vec![];
+        for item in items {
+            let syn::Item::Use(item) = item else {
+                continue;
+            };
+            for path in syn_util::flatten_use_tree(&item.tree) {
+                if let Some(name) = path.name {
+                    imports
+                        .push((name, scope.resolve(&path.segments, item.leading_colon.is_some())));
+                }
+            }
+        }
+        scope.names.extend(imports);
+        scope
+    }
+
+    /// Resolves the paths imported by a `use` item.
+    pub fn resolve_use(&self, item: &syn::ItemUse) -> Vec<String> {
+        syn_util::flatten_use_tree(&item.tree)
+            .iter()
+            .map(|path| self.resolve(&path.segments, item.leading_colon.is_some()))
+            .collect()
+    }
+
+    /// Resolves a path in code, e.g., `ir::Mod::new`. Paths starting with
+    /// other capitalized names, e.g., `String::new` or `T::default`, are
+    /// types from the prelude or generic parameters and are dropped.
+    pub fn resolve_code_path(&self, path: &str) -> Option<String> {
+        let segments = path.split("::").map(ToOwned::to_owned).collect::<Vec<_>>();
+        let first = segments.first()?;
+        if first.starts_with(char::is_uppercase) && !self.names.contains_key(first) {
+            return None;
+        }
+        Some(self.resolve(&segments, false))
+    }
+
+    /// Resolves a path, e.g., `super::ir::Mod`. Paths with a leading `::` are
+    /// `global`.
+    pub fn resolve(&self, segments: &[String], global: bool) -> String {
+        let Some((first, rest)) = segments.split_first() else {
+            return self.module_id.clone();
+        };
+        let (base, rest) = match first.as_str() {
+            _ if global => (format!("{first}::{}", id::LIB), rest),
+            "crate" => (self.crate_id.clone(), rest),
+            "self" => (self.module_id.clone(), rest),
+            "super" => {
+                let supers = segments.iter().take_while(|segment| *segment == "super").count();
+                let mut base = self.module_id.as_str();
+                for _ in 0..supers {
+                    // `super` cannot leave the crate.
+                    if base != self.crate_id {
+                        base = base.rsplit_once("::").map_or(base, |(parent, _)| parent);
+                    }
+                }
+                (base.to_owned(), &segments[supers..])
+            }
+            name => match self.names.get(name) {
+                Some(path) => (path.clone(), rest),
+                None => (format!("{name}::{}", id::LIB), rest),
+            },
+        };
+        rest.iter().fold(base, |path, segment| format!("{path}::{segment}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        let items = syn::parse_file(
+            "mod infra;
+            struct Db;
+            use super::domain::{self, Entity as E};
+            use serde::Serialize;",
+        )
+        .unwrap()
+        .items;
+        let scope = Scope::new("app::lib", "app::lib::db", &items);
+
+        let resolve = |path: &str| {
+            let item = syn::parse_str::<syn::ItemUse>(&format!("use {path};")).unwrap();
+            scope.resolve_use(&item)
+        };
+        assert_eq!(resolve("crate::api::Router"), ["app::lib::api::Router"]);
+        assert_eq!(resolve("self::infra::*"), ["app::lib::db::infra"]);
+        assert_eq!(resolve("super::super::super::Config"), ["app::lib::Config"]);
+        assert_eq!(
+            resolve("infra::{Conn, Pool}"),
+            ["app::lib::db::infra::Conn", "app::lib::db::infra::Pool"]
+        );
+        assert_eq!(resolve("E::Kind"), ["app::lib::domain::Entity::Kind"]);
+        assert_eq!(resolve("domain::Id"), ["app::lib::domain::Id"]);
+        assert_eq!(resolve("std::fmt"), ["std::lib::fmt"]);
+        assert_eq!(resolve("::infra::Conn"), ["infra::lib::Conn"]);
+
+        assert_eq!(scope.resolve_code_path("Db::new").as_deref(), Some("app::lib::db::Db::new"));
+        assert_eq!(
+            scope.resolve_code_path("E::Kind").as_deref(),
+            Some("app::lib::domain::Entity::Kind")
+        );
+        assert_eq!(scope.resolve_code_path("String::new"), None);
+    }
+}